@@ -65,7 +65,7 @@ class CbfSyncTest {
                 } catch (e: CbfException) { println("Info channel closed: $e") }
             }
 
-            val update: Update = withTimeout(60.seconds) { client.update() }
+            val update: Update = requireNotNull(withTimeout(60.seconds) { client.update() })
             wallet.applyUpdate(update)
 
             val balance = wallet.balance().total.toSat()
@@ -1,15 +1,21 @@
 use crate::error::{
     AddressParseError, Bip32Error, ExtractTxError, FeeRateError, FromScriptError, HashParseError,
-    PsbtError, PsbtParseError, TransactionError,
+    PsbtError, PsbtParseError, SilentPaymentAddressError, TransactionError,
 };
 use crate::error::{ParseAmountError, PsbtFinalizeError};
 use crate::keys::DerivationPath;
+use crate::types::MerkleProof;
 
 use crate::{impl_from_core_type, impl_hash_like, impl_into_core_type};
 use bdk_wallet::bitcoin::address::NetworkChecked;
 use bdk_wallet::bitcoin::address::NetworkUnchecked;
-use bdk_wallet::bitcoin::address::{Address as BdkAddress, AddressData as BdkAddressData};
+use bdk_wallet::bitcoin::address::{
+    Address as BdkAddress, AddressData as BdkAddressData, AddressType as BdkAddressType,
+};
+use bdk_wallet::bitcoin::bech32::primitives::decode::UncheckedHrpstring;
+use bdk_wallet::bitcoin::bech32::Checksum;
 use bdk_wallet::bitcoin::bip32::ChildNumber as BdkChildNumber;
+use bdk_wallet::bitcoin::bip32::KeySource as BdkKeySource;
 use bdk_wallet::bitcoin::blockdata::block::Block as BdkBlock;
 use bdk_wallet::bitcoin::blockdata::block::Header as BdkHeader;
 use bdk_wallet::bitcoin::consensus::encode::deserialize;
@@ -20,12 +26,15 @@ use bdk_wallet::bitcoin::hashes::sha256d::Hash as BitcoinDoubleSha256Hash;
 use bdk_wallet::bitcoin::io::Cursor;
 use bdk_wallet::bitcoin::psbt::Input as BdkInput;
 use bdk_wallet::bitcoin::psbt::Output as BdkOutput;
+use bdk_wallet::bitcoin::secp256k1::PublicKey as BdkPublicKey;
 use bdk_wallet::bitcoin::secp256k1::Secp256k1;
+use bdk_wallet::bitcoin::sign_message::{signed_msg_hash, MessageSignature};
 use bdk_wallet::bitcoin::taproot::LeafNode as BdkLeafNode;
 use bdk_wallet::bitcoin::taproot::NodeInfo as BdkNodeInfo;
 use bdk_wallet::bitcoin::taproot::TapTree as BdkTapTree;
 use bdk_wallet::bitcoin::Amount as BdkAmount;
 use bdk_wallet::bitcoin::BlockHash as BitcoinBlockHash;
+use bdk_wallet::bitcoin::Denomination;
 use bdk_wallet::bitcoin::FeeRate as BdkFeeRate;
 use bdk_wallet::bitcoin::OutPoint as BdkOutPoint;
 use bdk_wallet::bitcoin::Psbt as BdkPsbt;
@@ -38,8 +47,9 @@ use bdk_wallet::bitcoin::Weight;
 use bdk_wallet::bitcoin::Wtxid as BitcoinWtxid;
 use bdk_wallet::miniscript::psbt::PsbtExt;
 use bdk_wallet::serde_json;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 use std::fmt::Display;
 use std::fs::File;
@@ -267,6 +277,16 @@ impl Script {
     pub fn to_bytes(&self) -> Vec<u8> {
         self.0.to_bytes()
     }
+
+    /// Render the script in human-readable assembly, for example `OP_RETURN OP_PUSHBYTES_4 deadbeef`.
+    pub fn to_asm_string(&self) -> String {
+        self.0.to_asm_string()
+    }
+
+    /// Whether the script is an unspendable `OP_RETURN` data carrier.
+    pub fn is_op_return(&self) -> bool {
+        self.0.is_op_return()
+    }
 }
 
 impl_from_core_type!(BdkScriptBuf, Script);
@@ -312,25 +332,62 @@ impl From<BdkHeader> for Header {
 
 /// Bitcoin block.
 /// A collection of transactions with an attached proof of work.
-#[derive(uniffi::Record)]
-pub struct Block {
-    pub header: Header,
-    pub txdata: Vec<Arc<Transaction>>,
-}
-
-impl From<BdkBlock> for Block {
-    fn from(bdk_block: BdkBlock) -> Self {
-        Block {
-            header: bdk_block.header.into(),
-            txdata: bdk_block
-                .txdata
-                .into_iter()
-                .map(|tx| Arc::new(tx.into()))
-                .collect(),
-        }
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Object)]
+pub struct Block(pub(crate) BdkBlock);
+
+impl_from_core_type!(BdkBlock, Block);
+impl_into_core_type!(Block, BdkBlock);
+
+#[uniffi::export]
+impl Block {
+    /// Parse a block from its consensus encoding, such as the raw block returned by the
+    /// `getblock` RPC of a node with verbosity 0.
+    #[uniffi::constructor]
+    pub fn from_bytes(block_bytes: Vec<u8>) -> Result<Self, TransactionError> {
+        let block: BdkBlock = deserialize(&block_bytes)?;
+        Ok(Block(block))
+    }
+
+    /// The block header.
+    pub fn header(&self) -> Header {
+        self.0.header.into()
+    }
+
+    /// The transactions in the block.
+    pub fn txdata(&self) -> Vec<Arc<Transaction>> {
+        self.0
+            .txdata
+            .iter()
+            .map(|tx| Arc::new(tx.clone().into()))
+            .collect()
+    }
+
+    /// The hash of the block header.
+    pub fn block_hash(&self) -> Arc<BlockHash> {
+        Arc::new(BlockHash(self.0.block_hash()))
+    }
+
+    /// Serialize the block with its consensus encoding.
+    pub fn serialize(&self) -> Vec<u8> {
+        serialize(&self.0)
     }
 }
 
+/// The standard output type an address pays to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum AddressType {
+    /// Pay to public key hash.
+    P2pkh,
+    /// Pay to script hash.
+    P2sh,
+    /// Pay to witness public key hash.
+    P2wpkh,
+    /// Pay to witness script hash.
+    P2wsh,
+    /// Pay to taproot.
+    P2tr,
+}
+
 /// The type of address.
 #[derive(Debug, uniffi::Enum)]
 pub enum AddressData {
@@ -395,6 +452,19 @@ impl Address {
         }
     }
 
+    /// The standard output type of the address, or `None` for non-standard witness programs.
+    pub fn address_type(&self) -> Option<AddressType> {
+        match self.0.address_type()? {
+            BdkAddressType::P2pkh => Some(AddressType::P2pkh),
+            BdkAddressType::P2sh => Some(AddressType::P2sh),
+            BdkAddressType::P2wpkh => Some(AddressType::P2wpkh),
+            BdkAddressType::P2wsh => Some(AddressType::P2wsh),
+            BdkAddressType::P2tr => Some(AddressType::P2tr),
+            // AddressType is marked #[non_exhaustive] in bitcoin crate
+            _ => None,
+        }
+    }
+
     /// Return the data for the address.
     pub fn to_address_data(&self) -> AddressData {
         match self.0.to_address_data() {
@@ -414,6 +484,21 @@ impl Address {
             _ => unimplemented!("Unsupported address type"),
         }
     }
+
+    /// Verify a base64 encoded signature over a message in the legacy `Bitcoin Signed Message`
+    /// format, as produced by [`Wallet::sign_message`](crate::wallet::Wallet::sign_message).
+    /// Supports addresses controlled by a single key, like P2PKH and P2WPKH.
+    pub fn verify_message(&self, message: String, signature: String) -> bool {
+        let signature = match MessageSignature::from_base64(&signature) {
+            Ok(signature) => signature,
+            Err(_) => return false,
+        };
+        let secp = Secp256k1::verification_only();
+        signature
+            .recover_pubkey(&secp, signed_msg_hash(&message))
+            .map(|pubkey| self.0.is_related_to_pubkey(&pubkey))
+            .unwrap_or(false)
+    }
 }
 
 impl Display for Address {
@@ -425,6 +510,237 @@ impl Display for Address {
 impl_from_core_type!(BdkAddress, Address);
 impl_into_core_type!(Address, BdkAddress);
 
+/// Verify a base64 encoded signature over a message in the legacy `Bitcoin Signed Message`
+/// format. Shorthand for [`Address::verify_message`].
+#[uniffi::export]
+pub fn verify_message(address: Arc<Address>, message: String, signature: String) -> bool {
+    address.verify_message(message, signature)
+}
+
+/// A BIP-352 silent payment address, such as `sp1qq...`.
+///
+/// The address holds a scan key and a spend key. A sender derives a fresh output for every
+/// payment from them and the private keys of the inputs being spent, so no output ever pays the
+/// address itself. Deriving that output is not supported yet.
+#[derive(Debug, PartialEq, Eq, uniffi::Object)]
+#[uniffi::export(Eq, Display)]
+pub struct SilentPaymentAddress {
+    address: String,
+    version: u8,
+    scan_key: BdkPublicKey,
+    spend_key: BdkPublicKey,
+}
+
+#[uniffi::export]
+impl SilentPaymentAddress {
+    /// Parse a silent payment address and check that it is valid for `network`. Addresses use the
+    /// `sp` prefix on mainnet and `tsp` on the test networks.
+    ///
+    /// Versions 1 to 30 are parsed as version 0, ignoring any data past the two keys, as BIP-352
+    /// requires for forward compatibility. Version 31 is rejected.
+    #[uniffi::constructor]
+    pub fn new(address: String, network: Network) -> Result<Self, SilentPaymentAddressError> {
+        let unchecked =
+            UncheckedHrpstring::new(&address).map_err(|e| SilentPaymentAddressError::Bech32 {
+                error_message: e.to_string(),
+            })?;
+        let expected_hrp = match unchecked.hrp().to_lowercase().as_str() {
+            "sp" => NetworkKind::Main,
+            "tsp" => NetworkKind::Test,
+            hrp => {
+                return Err(SilentPaymentAddressError::UnknownHrp {
+                    hrp: hrp.to_string(),
+                })
+            }
+        };
+        let mut checked = unchecked
+            .validate_and_remove_checksum::<SilentPaymentChecksum>()
+            .map_err(|e| SilentPaymentAddressError::Bech32 {
+                error_message: e.to_string(),
+            })?;
+        if NetworkKind::from(network) != expected_hrp {
+            return Err(SilentPaymentAddressError::NetworkValidation);
+        }
+
+        let version = checked
+            .remove_witness_version()
+            .ok_or(SilentPaymentAddressError::InvalidLength { length: 0 })?
+            .to_u8();
+        if version == 31 {
+            return Err(SilentPaymentAddressError::UnsupportedVersion { version });
+        }
+        let payload: Vec<u8> = checked.byte_iter().collect();
+        if payload.len() < 66 || (version == 0 && payload.len() != 66) {
+            return Err(SilentPaymentAddressError::InvalidLength {
+                length: payload.len() as u32,
+            });
+        }
+        let public_key = |bytes: &[u8]| {
+            BdkPublicKey::from_slice(bytes).map_err(|e| {
+                SilentPaymentAddressError::InvalidPublicKey {
+                    error_message: e.to_string(),
+                }
+            })
+        };
+
+        Ok(SilentPaymentAddress {
+            address: address.to_lowercase(),
+            version,
+            scan_key: public_key(&payload[..33])?,
+            spend_key: public_key(&payload[33..66])?,
+        })
+    }
+
+    /// The version of the address format.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// The compressed public key the receiver scans the chain with.
+    pub fn scan_key(&self) -> Vec<u8> {
+        self.scan_key.serialize().to_vec()
+    }
+
+    /// The compressed public key the outputs paying the receiver are tweaked from.
+    pub fn spend_key(&self) -> Vec<u8> {
+        self.spend_key.serialize().to_vec()
+    }
+}
+
+impl Display for SilentPaymentAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.address)
+    }
+}
+
+/// Bech32m with the 1023 character limit BIP-352 sets for silent payment addresses, in place of
+/// the 90 characters allowed for segwit addresses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, std::hash::Hash)]
+enum SilentPaymentChecksum {}
+
+impl Checksum for SilentPaymentChecksum {
+    type MidstateRepr = u32;
+    const CODE_LENGTH: usize = 1023;
+    const CHECKSUM_LENGTH: usize = 6;
+    const GENERATOR_SH: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    const TARGET_RESIDUE: u32 = 0x2bc8_30a3;
+}
+
+const BIP21_SCHEME: &str = "bitcoin:";
+
+/// The characters escaped in BIP-21 parameter values: everything but the unreserved characters
+/// of RFC 3986.
+const BIP21_ESCAPED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// A BIP-21 payment request, like `bitcoin:<address>?amount=0.001&label=Coffee`, as shown in a
+/// QR code on a receive screen.
+#[derive(Debug, uniffi::Record)]
+pub struct Bip21 {
+    /// The address to pay.
+    pub address: Arc<Address>,
+    /// The requested amount, if any.
+    pub amount: Option<Arc<Amount>>,
+    /// A label for the address, like the name of the receiver.
+    pub label: Option<String>,
+    /// A message describing the payment.
+    pub message: Option<String>,
+}
+
+/// Build a payment request for an address, with an optional amount, label and message.
+#[uniffi::export(default(amount = None, label = None, message = None))]
+pub fn build_bip21_uri(
+    address: Arc<Address>,
+    amount: Option<Arc<Amount>>,
+    label: Option<String>,
+    message: Option<String>,
+) -> String {
+    let mut parameters = Vec::new();
+    if let Some(amount) = amount {
+        parameters.push(format!(
+            "amount={}",
+            amount.0.to_string_in(Denomination::Bitcoin)
+        ));
+    }
+    if let Some(label) = label {
+        parameters.push(format!(
+            "label={}",
+            utf8_percent_encode(&label, BIP21_ESCAPED)
+        ));
+    }
+    if let Some(message) = message {
+        parameters.push(format!(
+            "message={}",
+            utf8_percent_encode(&message, BIP21_ESCAPED)
+        ));
+    }
+    let mut uri = format!("{}{}", BIP21_SCHEME, address);
+    if !parameters.is_empty() {
+        uri.push('?');
+        uri.push_str(&parameters.join("&"));
+    }
+    uri
+}
+
+/// Parse a payment request. Unknown parameters are ignored, unless prefixed with `req-`, which
+/// must be understood by the wallet and are rejected.
+///
+/// The network of the address is not checked, so check it with `Address::is_valid_for_network`
+/// before paying.
+#[uniffi::export]
+pub fn parse_bip21_uri(uri: String) -> Result<Bip21, AddressParseError> {
+    let invalid = |error_message: String| AddressParseError::InvalidBip21Uri { error_message };
+    let uri = match uri.get(..BIP21_SCHEME.len()) {
+        Some(scheme) if scheme.eq_ignore_ascii_case(BIP21_SCHEME) => &uri[BIP21_SCHEME.len()..],
+        _ => {
+            return Err(invalid(
+                "the uri does not use the bitcoin scheme".to_string(),
+            ))
+        }
+    };
+    let (address, query) = uri.split_once('?').unwrap_or((uri, ""));
+    let address = address
+        .parse::<BdkAddress<NetworkUnchecked>>()?
+        .assume_checked();
+
+    let mut payment_request = Bip21 {
+        address: Arc::new(Address(address)),
+        amount: None,
+        label: None,
+        message: None,
+    };
+    for parameter in query.split('&').filter(|parameter| !parameter.is_empty()) {
+        let (key, value) = parameter.split_once('=').unwrap_or((parameter, ""));
+        let value = percent_decode_str(value)
+            .decode_utf8()
+            .map_err(|_| invalid(format!("invalid percent encoding: {value}")))?
+            .into_owned();
+        match key {
+            "amount" => {
+                let amount = BdkAmount::from_str_in(&value, Denomination::Bitcoin)
+                    .map_err(|_| invalid(format!("invalid amount: {value}")))?;
+                payment_request.amount = Some(Arc::new(Amount(amount)));
+            }
+            "label" => payment_request.label = Some(value),
+            "message" => payment_request.message = Some(value),
+            key if key.starts_with("req-") => {
+                return Err(invalid(format!("unsupported required parameter: {key}")))
+            }
+            _ => (),
+        }
+    }
+    Ok(payment_request)
+}
+
 /// Bitcoin transaction.
 /// An authenticated movement of coins.
 #[derive(Debug, Clone, PartialEq, Eq, uniffi::Object)]
@@ -543,6 +859,16 @@ impl Transaction {
         self.0.output.iter().map(|tx_out| tx_out.into()).collect()
     }
 
+    /// The number of transaction inputs.
+    pub fn input_count(&self) -> u64 {
+        self.0.input.len() as u64
+    }
+
+    /// The number of transaction outputs.
+    pub fn output_count(&self) -> u64 {
+        self.0.output.len() as u64
+    }
+
     /// Block height or timestamp. Transaction cannot be included in a block until this height/time.
     ///
     /// /// ### Relevant BIPs
@@ -725,19 +1051,7 @@ impl From<&BdkInput> for Input {
                 .witness_script
                 .as_ref()
                 .map(|s| Arc::new(Script(s.clone()))),
-            bip32_derivation: input
-                .bip32_derivation
-                .iter()
-                .map(|(pk, (fingerprint, deriv_path))| {
-                    (
-                        pk.to_string(),
-                        KeySource {
-                            fingerprint: fingerprint.to_string(),
-                            path: Arc::new(deriv_path.clone().into()),
-                        },
-                    )
-                })
-                .collect(),
+            bip32_derivation: key_sources(&input.bip32_derivation),
             final_script_sig: input
                 .final_script_sig
                 .as_ref()
@@ -1286,6 +1600,55 @@ impl Display for NodeInfo {
     }
 }
 
+/// What a signing device shows about an input of a PSBT before signing it.
+#[derive(Debug, uniffi::Record)]
+pub struct PsbtInputInfo {
+    /// The output this input spends.
+    pub previous_output: OutPoint,
+    /// The value of the spent output, if the PSBT carries the output.
+    pub value: Option<Arc<Amount>>,
+    /// The script of the spent output, if the PSBT carries the output.
+    pub script_pubkey: Option<Arc<Script>>,
+    /// The master key fingerprints and derivation paths of the keys that sign this input.
+    pub bip32_derivation: HashMap<String, KeySource>,
+    /// Whether the PSBT lists derivation paths for the keys of this input, which a wallet only
+    /// does for its own coins. Use `Wallet.is_mine` to check against a particular wallet.
+    pub is_mine: bool,
+}
+
+/// What a signing device shows about an output of a PSBT before signing it.
+#[derive(Debug, uniffi::Record)]
+pub struct PsbtOutputInfo {
+    /// The value of the output.
+    pub amount: Arc<Amount>,
+    /// The script the output pays to.
+    pub script_pubkey: Arc<Script>,
+    /// The master key fingerprints and derivation paths of the keys that control this output.
+    pub bip32_derivation: HashMap<String, KeySource>,
+    /// Whether the PSBT lists derivation paths for the keys of this output. A wallet only does so
+    /// for its own scripts, so outputs flagged this way are change or self-transfers rather than
+    /// payments. Use `Wallet.derivation_of_spk` to tell change from a self-transfer.
+    pub is_mine: bool,
+}
+
+/// Convert the BIP-32 key origins of a PSBT map, keyed by the public key.
+fn key_sources(
+    bip32_derivation: &BTreeMap<BdkPublicKey, BdkKeySource>,
+) -> HashMap<String, KeySource> {
+    bip32_derivation
+        .iter()
+        .map(|(pk, (fingerprint, deriv_path))| {
+            (
+                pk.to_string(),
+                KeySource {
+                    fingerprint: fingerprint.to_string(),
+                    path: Arc::new(deriv_path.clone().into()),
+                },
+            )
+        })
+        .collect()
+}
+
 /// A key-value map for an output of the corresponding index in the unsigned
 /// transaction.
 #[derive(Debug, uniffi::Record)]
@@ -1320,19 +1683,7 @@ impl From<&BdkOutput> for Output {
                 .witness_script
                 .as_ref()
                 .map(|s| Arc::new(Script(s.clone()))),
-            bip32_derivation: output
-                .bip32_derivation
-                .iter()
-                .map(|(pk, (fingerprint, deriv_path))| {
-                    (
-                        pk.to_string(),
-                        KeySource {
-                            fingerprint: fingerprint.to_string(),
-                            path: Arc::new(deriv_path.clone().into()),
-                        },
-                    )
-                })
-                .collect(),
+            bip32_derivation: key_sources(&output.bip32_derivation),
             tap_internal_key: output.tap_internal_key.as_ref().map(|k| k.to_string()),
             tap_tree: output
                 .tap_tree
@@ -1400,6 +1751,15 @@ impl Psbt {
         Ok(Psbt(Mutex::new(psbt)))
     }
 
+    /// Creates a new `Psbt` instance from its binary BIP-174 encoding.
+    #[uniffi::constructor]
+    pub fn from_bytes(psbt_bytes: Vec<u8>) -> Result<Self, PsbtParseError> {
+        let psbt = BdkPsbt::deserialize(&psbt_bytes).map_err(|e| PsbtParseError::PsbtEncoding {
+            error_message: e.to_string(),
+        })?;
+        Ok(Psbt(Mutex::new(psbt)))
+    }
+
     /// Creates a PSBT from an unsigned transaction.
     ///
     /// # Errors
@@ -1426,6 +1786,11 @@ impl Psbt {
         psbt.to_string()
     }
 
+    /// Serialize the PSBT into its binary BIP-174 encoding, as used in files and animated QR codes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.lock().unwrap().serialize()
+    }
+
     /// Extracts the `Transaction` from a `Psbt` by filling in the available signature information.
     ///
     /// #### Errors
@@ -1491,6 +1856,16 @@ impl Psbt {
         }
     }
 
+    /// Whether each input, in order, carries a final `scriptSig` or witness. Useful to show how
+    /// many inputs of a multi-party transaction are ready.
+    pub fn finalized_inputs(&self) -> Vec<bool> {
+        let psbt = self.0.lock().unwrap();
+        psbt.inputs
+            .iter()
+            .map(|input| input.final_script_sig.is_some() || input.final_script_witness.is_some())
+            .collect()
+    }
+
     /// Write the `Psbt` to a file. Note that the file must not yet exist.
     pub fn write_to_file(&self, path: String) -> Result<(), PsbtError> {
         let file = File::create_new(path)?;
@@ -1524,6 +1899,45 @@ impl Psbt {
         let psbt = self.0.lock().unwrap();
         psbt.outputs.iter().map(|o| o.into()).collect()
     }
+
+    /// The value, script and key origins of each input, for display on a signing device.
+    pub fn inputs(&self) -> Vec<PsbtInputInfo> {
+        let psbt = self.0.lock().unwrap();
+        psbt.unsigned_tx
+            .input
+            .iter()
+            .zip(&psbt.inputs)
+            .enumerate()
+            .map(|(index, (txin, input))| {
+                let prevout = psbt.spend_utxo(index).ok();
+                PsbtInputInfo {
+                    previous_output: (&txin.previous_output).into(),
+                    value: prevout.map(|txout| Arc::new(Amount(txout.value))),
+                    script_pubkey: prevout
+                        .map(|txout| Arc::new(Script(txout.script_pubkey.clone()))),
+                    bip32_derivation: key_sources(&input.bip32_derivation),
+                    is_mine: !input.bip32_derivation.is_empty()
+                        || !input.tap_key_origins.is_empty(),
+                }
+            })
+            .collect()
+    }
+
+    /// The value, script and key origins of each output, for display on a signing device.
+    pub fn outputs(&self) -> Vec<PsbtOutputInfo> {
+        let psbt = self.0.lock().unwrap();
+        psbt.unsigned_tx
+            .output
+            .iter()
+            .zip(&psbt.outputs)
+            .map(|(txout, output)| PsbtOutputInfo {
+                amount: Arc::new(Amount(txout.value)),
+                script_pubkey: Arc::new(Script(txout.script_pubkey.clone())),
+                bip32_derivation: key_sources(&output.bip32_derivation),
+                is_mine: !output.bip32_derivation.is_empty() || !output.tap_key_origins.is_empty(),
+            })
+            .collect()
+    }
 }
 
 impl From<BdkPsbt> for Psbt {
@@ -1683,6 +2097,32 @@ pub struct TxMerkleNode(pub(crate) BitcoinDoubleSha256Hash);
 
 impl_hash_like!(TxMerkleNode, BitcoinDoubleSha256Hash);
 
+#[uniffi::export]
+impl TxMerkleNode {
+    /// Check that `proof` shows `txid` is committed to by this merkle root, i.e. that the
+    /// transaction is included in a block whose header carries this root.
+    pub fn verify_merkle_proof(&self, txid: Arc<Txid>, proof: MerkleProof) -> bool {
+        use bdk_wallet::bitcoin::hashes::{Hash, HashEngine};
+
+        let mut index = proof.pos;
+        let mut current = txid.0.to_raw_hash();
+        for sibling in proof.merkle {
+            let sibling = sibling.0.to_raw_hash();
+            let mut engine = BitcoinDoubleSha256Hash::engine();
+            if index % 2 == 0 {
+                engine.input(current.as_ref());
+                engine.input(sibling.as_ref());
+            } else {
+                engine.input(sibling.as_ref());
+                engine.input(current.as_ref());
+            }
+            current = BitcoinDoubleSha256Hash::from_engine(engine);
+            index /= 2;
+        }
+        current == self.0
+    }
+}
+
 /// Descriptor Type of the descriptor
 #[uniffi::remote(Enum)]
 pub enum DescriptorType {
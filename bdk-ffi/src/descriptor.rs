@@ -1,16 +1,20 @@
 use crate::bitcoin::DescriptorId;
 use crate::bitcoin::DescriptorType;
-use crate::bitcoin::{Address, NetworkKind};
+use crate::bitcoin::{Address, Amount, NetworkKind};
 use crate::error::DescriptorError;
 use crate::error::MiniscriptError;
+use crate::keys::check_bip48_key;
 use crate::keys::DescriptorPublicKey;
 use crate::keys::DescriptorSecretKey;
-use crate::types::KeychainKind;
+use crate::types::{
+    Bip48ScriptType, DescriptorInfo, DescriptorScriptType, KeychainKind, MultisigScriptType,
+};
 
 use bdk_wallet::bitcoin::bip32::Fingerprint;
 use bdk_wallet::bitcoin::key::Secp256k1;
 use bdk_wallet::bitcoin::Network;
 use bdk_wallet::chain::DescriptorExt;
+use bdk_wallet::descriptor::checksum::calc_checksum;
 use bdk_wallet::descriptor::{ExtendedDescriptor, IntoWalletDescriptor};
 use bdk_wallet::keys::DescriptorPublicKey as BdkDescriptorPublicKey;
 use bdk_wallet::keys::{DescriptorSecretKey as BdkDescriptorSecretKey, KeyMap};
@@ -53,26 +57,22 @@ impl Descriptor {
         secret_key: &DescriptorSecretKey,
         keychain_kind: KeychainKind,
         network_kind: NetworkKind,
-    ) -> Self {
+    ) -> Result<Self, DescriptorError> {
         let derivable_key = &secret_key.0;
 
         match derivable_key {
-            BdkDescriptorSecretKey::Single(_) => {
-                unreachable!()
-            }
+            BdkDescriptorSecretKey::Single(_) => Err(DescriptorError::InvalidKeyType),
             BdkDescriptorSecretKey::XPrv(descriptor_x_key) => {
                 let derivable_key = descriptor_x_key.xkey;
                 let (extended_descriptor, key_map, _) = Bip44(derivable_key, keychain_kind)
                     .build(network_kind)
-                    .unwrap();
-                Self {
+                    .map_err(DescriptorError::from)?;
+                Ok(Self {
                     extended_descriptor,
                     key_map,
-                }
-            }
-            BdkDescriptorSecretKey::MultiXPrv(_) => {
-                unreachable!()
+                })
             }
+            BdkDescriptorSecretKey::MultiXPrv(_) => Err(DescriptorError::InvalidKeyType),
         }
     }
 
@@ -85,16 +85,14 @@ impl Descriptor {
         network_kind: NetworkKind,
     ) -> Result<Self, DescriptorError> {
         let fingerprint = Fingerprint::from_str(fingerprint.as_str()).map_err(|error| {
-            DescriptorError::Bip32 {
+            DescriptorError::InvalidFingerprint {
                 error_message: error.to_string(),
             }
         })?;
         let derivable_key = &public_key.0;
 
         match derivable_key {
-            BdkDescriptorPublicKey::Single(_) => {
-                unreachable!()
-            }
+            BdkDescriptorPublicKey::Single(_) => Err(DescriptorError::InvalidKeyType),
             BdkDescriptorPublicKey::XPub(descriptor_x_key) => {
                 let derivable_key = descriptor_x_key.xkey;
                 let (extended_descriptor, key_map, _) =
@@ -107,9 +105,7 @@ impl Descriptor {
                     key_map,
                 })
             }
-            BdkDescriptorPublicKey::MultiXPub(_) => {
-                unreachable!()
-            }
+            BdkDescriptorPublicKey::MultiXPub(_) => Err(DescriptorError::InvalidKeyType),
         }
     }
 
@@ -119,26 +115,22 @@ impl Descriptor {
         secret_key: &DescriptorSecretKey,
         keychain_kind: KeychainKind,
         network_kind: NetworkKind,
-    ) -> Self {
+    ) -> Result<Self, DescriptorError> {
         let derivable_key = &secret_key.0;
 
         match derivable_key {
-            BdkDescriptorSecretKey::Single(_) => {
-                unreachable!()
-            }
+            BdkDescriptorSecretKey::Single(_) => Err(DescriptorError::InvalidKeyType),
             BdkDescriptorSecretKey::XPrv(descriptor_x_key) => {
                 let derivable_key = descriptor_x_key.xkey;
                 let (extended_descriptor, key_map, _) = Bip49(derivable_key, keychain_kind)
                     .build(network_kind)
-                    .unwrap();
-                Self {
+                    .map_err(DescriptorError::from)?;
+                Ok(Self {
                     extended_descriptor,
                     key_map,
-                }
-            }
-            BdkDescriptorSecretKey::MultiXPrv(_) => {
-                unreachable!()
+                })
             }
+            BdkDescriptorSecretKey::MultiXPrv(_) => Err(DescriptorError::InvalidKeyType),
         }
     }
 
@@ -151,16 +143,14 @@ impl Descriptor {
         network_kind: NetworkKind,
     ) -> Result<Self, DescriptorError> {
         let fingerprint = Fingerprint::from_str(fingerprint.as_str()).map_err(|error| {
-            DescriptorError::Bip32 {
+            DescriptorError::InvalidFingerprint {
                 error_message: error.to_string(),
             }
         })?;
         let derivable_key = &public_key.0;
 
         match derivable_key {
-            BdkDescriptorPublicKey::Single(_) => {
-                unreachable!()
-            }
+            BdkDescriptorPublicKey::Single(_) => Err(DescriptorError::InvalidKeyType),
             BdkDescriptorPublicKey::XPub(descriptor_x_key) => {
                 let derivable_key = descriptor_x_key.xkey;
                 let (extended_descriptor, key_map, _) =
@@ -173,9 +163,7 @@ impl Descriptor {
                     key_map,
                 })
             }
-            BdkDescriptorPublicKey::MultiXPub(_) => {
-                unreachable!()
-            }
+            BdkDescriptorPublicKey::MultiXPub(_) => Err(DescriptorError::InvalidKeyType),
         }
     }
 
@@ -185,26 +173,22 @@ impl Descriptor {
         secret_key: &DescriptorSecretKey,
         keychain_kind: KeychainKind,
         network_kind: NetworkKind,
-    ) -> Self {
+    ) -> Result<Self, DescriptorError> {
         let derivable_key = &secret_key.0;
 
         match derivable_key {
-            BdkDescriptorSecretKey::Single(_) => {
-                unreachable!()
-            }
+            BdkDescriptorSecretKey::Single(_) => Err(DescriptorError::InvalidKeyType),
             BdkDescriptorSecretKey::XPrv(descriptor_x_key) => {
                 let derivable_key = descriptor_x_key.xkey;
                 let (extended_descriptor, key_map, _) = Bip84(derivable_key, keychain_kind)
                     .build(network_kind)
-                    .unwrap();
-                Self {
+                    .map_err(DescriptorError::from)?;
+                Ok(Self {
                     extended_descriptor,
                     key_map,
-                }
-            }
-            BdkDescriptorSecretKey::MultiXPrv(_) => {
-                unreachable!()
+                })
             }
+            BdkDescriptorSecretKey::MultiXPrv(_) => Err(DescriptorError::InvalidKeyType),
         }
     }
 
@@ -217,16 +201,14 @@ impl Descriptor {
         network_kind: NetworkKind,
     ) -> Result<Self, DescriptorError> {
         let fingerprint = Fingerprint::from_str(fingerprint.as_str()).map_err(|error| {
-            DescriptorError::Bip32 {
+            DescriptorError::InvalidFingerprint {
                 error_message: error.to_string(),
             }
         })?;
         let derivable_key = &public_key.0;
 
         match derivable_key {
-            BdkDescriptorPublicKey::Single(_) => {
-                unreachable!()
-            }
+            BdkDescriptorPublicKey::Single(_) => Err(DescriptorError::InvalidKeyType),
             BdkDescriptorPublicKey::XPub(descriptor_x_key) => {
                 let derivable_key = descriptor_x_key.xkey;
                 let (extended_descriptor, key_map, _) =
@@ -239,9 +221,7 @@ impl Descriptor {
                     key_map,
                 })
             }
-            BdkDescriptorPublicKey::MultiXPub(_) => {
-                unreachable!()
-            }
+            BdkDescriptorPublicKey::MultiXPub(_) => Err(DescriptorError::InvalidKeyType),
         }
     }
 
@@ -251,26 +231,22 @@ impl Descriptor {
         secret_key: &DescriptorSecretKey,
         keychain_kind: KeychainKind,
         network_kind: NetworkKind,
-    ) -> Self {
+    ) -> Result<Self, DescriptorError> {
         let derivable_key = &secret_key.0;
 
         match derivable_key {
-            BdkDescriptorSecretKey::Single(_) => {
-                unreachable!()
-            }
+            BdkDescriptorSecretKey::Single(_) => Err(DescriptorError::InvalidKeyType),
             BdkDescriptorSecretKey::XPrv(descriptor_x_key) => {
                 let derivable_key = descriptor_x_key.xkey;
                 let (extended_descriptor, key_map, _) = Bip86(derivable_key, keychain_kind)
                     .build(network_kind)
-                    .unwrap();
-                Self {
+                    .map_err(DescriptorError::from)?;
+                Ok(Self {
                     extended_descriptor,
                     key_map,
-                }
-            }
-            BdkDescriptorSecretKey::MultiXPrv(_) => {
-                unreachable!()
+                })
             }
+            BdkDescriptorSecretKey::MultiXPrv(_) => Err(DescriptorError::InvalidKeyType),
         }
     }
 
@@ -283,16 +259,14 @@ impl Descriptor {
         network_kind: NetworkKind,
     ) -> Result<Self, DescriptorError> {
         let fingerprint = Fingerprint::from_str(fingerprint.as_str()).map_err(|error| {
-            DescriptorError::Bip32 {
+            DescriptorError::InvalidFingerprint {
                 error_message: error.to_string(),
             }
         })?;
         let derivable_key = &public_key.0;
 
         match derivable_key {
-            BdkDescriptorPublicKey::Single(_) => {
-                unreachable!()
-            }
+            BdkDescriptorPublicKey::Single(_) => Err(DescriptorError::InvalidKeyType),
             BdkDescriptorPublicKey::XPub(descriptor_x_key) => {
                 let derivable_key = descriptor_x_key.xkey;
                 let (extended_descriptor, key_map, _) =
@@ -305,9 +279,7 @@ impl Descriptor {
                     key_map,
                 })
             }
-            BdkDescriptorPublicKey::MultiXPub(_) => {
-                unreachable!()
-            }
+            BdkDescriptorPublicKey::MultiXPub(_) => Err(DescriptorError::InvalidKeyType),
         }
     }
 
@@ -398,6 +370,95 @@ impl Descriptor {
         })
     }
 
+    /// Create a new `threshold`-of-n multisig descriptor from a list of public keys.
+    ///
+    /// When `sorted` is true the keys are sorted lexicographically when deriving scripts
+    /// (`sortedmulti`), otherwise they are used in the order provided (`multi`). Errors when the
+    /// threshold is zero or larger than the number of keys, or when a key does not match the
+    /// network.
+    #[uniffi::constructor]
+    pub fn new_multisig(
+        threshold: u64,
+        keys: Vec<Arc<DescriptorPublicKey>>,
+        sorted: bool,
+        script_type: MultisigScriptType,
+        network_kind: NetworkKind,
+    ) -> Result<Self, DescriptorError> {
+        if threshold == 0 || threshold > keys.len() as u64 {
+            return Err(DescriptorError::InvalidThreshold {
+                threshold,
+                key_count: keys.len() as u64,
+            });
+        }
+
+        let fragment = if sorted { "sortedmulti" } else { "multi" };
+        let keys = keys
+            .iter()
+            .map(|key| key.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        let multi = format!("{fragment}({threshold},{keys})");
+        let descriptor = match script_type {
+            MultisigScriptType::Wsh => format!("wsh({multi})"),
+            MultisigScriptType::ShWsh => format!("sh(wsh({multi}))"),
+        };
+
+        Self::new(descriptor, network_kind)
+    }
+
+    /// Multisig account descriptor as defined in BIP-48: https://github.com/bitcoin/bips/blob/master/bip-0048.mediawiki
+    ///
+    /// Derives the account key of `secret_key` like `DescriptorSecretKey::new_bip48` and combines
+    /// it with the `cosigner_keys` in a `threshold`-of-n `sortedmulti` descriptor. The cosigner
+    /// keys must carry a BIP-48 origin for the same script type, as created by
+    /// `DescriptorPublicKey::new_bip48_public`.
+    #[uniffi::constructor]
+    pub fn new_bip48(
+        threshold: u64,
+        secret_key: &DescriptorSecretKey,
+        cosigner_keys: Vec<Arc<DescriptorPublicKey>>,
+        keychain_kind: KeychainKind,
+        account: u32,
+        script_type: Bip48ScriptType,
+        network_kind: NetworkKind,
+    ) -> Result<Self, DescriptorError> {
+        let account_key = DescriptorSecretKey::new_bip48(
+            secret_key,
+            keychain_kind,
+            account,
+            script_type,
+            network_kind,
+        )?;
+        let mut keys = vec![account_key.to_string()];
+        for key in cosigner_keys {
+            check_bip48_key(&key.0, script_type, network_kind)?;
+            keys.push(key.to_string());
+        }
+        bip48_multisig(threshold, keys, script_type, network_kind)
+    }
+
+    /// Multisig account descriptor as defined in BIP-48: https://github.com/bitcoin/bips/blob/master/bip-0048.mediawiki
+    ///
+    /// Combines the `public_keys` in a `threshold`-of-n `sortedmulti` descriptor. Every key must
+    /// carry a BIP-48 origin for `script_type`, as created by
+    /// `DescriptorPublicKey::new_bip48_public`.
+    #[uniffi::constructor]
+    pub fn new_bip48_public(
+        threshold: u64,
+        public_keys: Vec<Arc<DescriptorPublicKey>>,
+        script_type: Bip48ScriptType,
+        network_kind: NetworkKind,
+    ) -> Result<Self, DescriptorError> {
+        let keys = public_keys
+            .iter()
+            .map(|key| {
+                check_bip48_key(&key.0, script_type, network_kind)?;
+                Ok(key.to_string())
+            })
+            .collect::<Result<Vec<String>, DescriptorError>>()?;
+        bip48_multisig(threshold, keys, script_type, network_kind)
+    }
+
     /// Create a new pay-to-pubkey descriptor from a public key string.
     #[uniffi::constructor]
     pub fn new_pk(pk: String) -> Result<Self, DescriptorError> {
@@ -602,6 +663,20 @@ impl Descriptor {
         })
     }
 
+    /// Create a new taproot descriptor with a script tree, `tr(internal_key,script_tree)`.
+    ///
+    /// The script tree uses the descriptor syntax, a single miniscript leaf like `pk(key)` or
+    /// branches in braces like `{pk(key1),{pk(key2),older(144)}}`. Errors when a key or leaf does
+    /// not parse, or when a leaf is not valid miniscript in the tapscript context.
+    #[uniffi::constructor]
+    pub fn new_tr(
+        internal_key: String,
+        script_tree: String,
+        network_kind: NetworkKind,
+    ) -> Result<Self, DescriptorError> {
+        Descriptor::new(format!("tr({internal_key},{script_tree})"), network_kind)
+    }
+
     /// Dangerously convert the descriptor to a string.
     pub fn to_string_with_secret(&self) -> String {
         let descriptor = &self.extended_descriptor;
@@ -655,6 +730,11 @@ impl Descriptor {
         self.extended_descriptor.desc_type()
     }
 
+    /// The type of output script this descriptor produces.
+    pub fn script_type(&self) -> DescriptorScriptType {
+        self.extended_descriptor.desc_type().into()
+    }
+
     pub fn derive_address(
         &self,
         index: u32,
@@ -682,6 +762,76 @@ impl Descriptor {
         Ok(Arc::new(address))
     }
 
+    /// Get the definite descriptor at the given derivation index.
+    ///
+    /// The wildcards of a ranged descriptor are replaced by `index`, producing a descriptor for a
+    /// single script. The returned descriptor does not carry any secret keys. Errors when the
+    /// descriptor is not ranged, is multipath, or when `index` is in the hardened range.
+    pub fn derived_descriptor(&self, index: u32) -> Result<Arc<Descriptor>, DescriptorError> {
+        if !self.extended_descriptor.has_wildcard() {
+            return Err(DescriptorError::NotRanged);
+        }
+        if self.extended_descriptor.is_multipath() {
+            return Err(DescriptorError::MultiPath);
+        }
+
+        let definite_descriptor = self
+            .extended_descriptor
+            .at_derivation_index(index)
+            .map_err(|error| match error {
+                ConversionError::HardenedChild => DescriptorError::HardenedDerivationXpub,
+                ConversionError::MultiKey => DescriptorError::MultiPath,
+            })?;
+        let extended_descriptor = ExtendedDescriptor::from_str(&definite_descriptor.to_string())
+            .map_err(|error| DescriptorError::Miniscript {
+                error_message: error.to_string(),
+            })?;
+
+        Ok(Arc::new(Descriptor {
+            extended_descriptor,
+            key_map: KeyMap::new(),
+        }))
+    }
+
+    /// The minimum amount an output paying to this descriptor must have to not be considered dust
+    /// under the default relay policy. The threshold is computed from the first script the
+    /// descriptor derives, as it only depends on the script type.
+    pub fn dust_value(&self) -> Arc<Amount> {
+        // every path of a multipath descriptor shares the script type, so the first one is used
+        let descriptor = self
+            .extended_descriptor
+            .clone()
+            .into_single_descriptors()
+            .ok()
+            .and_then(|descriptors| descriptors.into_iter().next())
+            .unwrap_or_else(|| self.extended_descriptor.clone());
+        Arc::new(descriptor.dust_value().into())
+    }
+
+    /// The BIP-380 checksum of the descriptor, the eight characters following the `#` of its
+    /// string form.
+    ///
+    /// To verify a checksum without parsing the descriptor, use `verify_descriptor_checksum`.
+    /// `Descriptor::new` also rejects a mismatch with `DescriptorError::InvalidDescriptorChecksum`.
+    pub fn checksum(&self) -> String {
+        let descriptor = self.extended_descriptor.to_string();
+        descriptor
+            .rsplit_once('#')
+            .map(|(_, checksum)| checksum.to_string())
+            .unwrap_or_default()
+    }
+
+    /// Summarize the descriptor: whether it is ranged or multipath, its script type and whether
+    /// it holds secret keys. See `inspect_descriptor` to summarize a descriptor string directly.
+    pub fn info(&self) -> DescriptorInfo {
+        DescriptorInfo {
+            is_ranged: self.extended_descriptor.has_wildcard(),
+            is_multipath: self.extended_descriptor.is_multipath(),
+            script_type: self.script_type(),
+            has_secret: !self.key_map.is_empty(),
+        }
+    }
+
     /// Whether or not the descriptor has any wildcards.
     pub fn has_wildcard(&self) -> bool {
         self.extended_descriptor.has_wildcard()
@@ -710,3 +860,45 @@ impl Display for Descriptor {
         write!(f, "{}", self.extended_descriptor)
     }
 }
+
+fn bip48_multisig(
+    threshold: u64,
+    keys: Vec<String>,
+    script_type: Bip48ScriptType,
+    network_kind: NetworkKind,
+) -> Result<Descriptor, DescriptorError> {
+    if threshold == 0 || threshold > keys.len() as u64 {
+        return Err(DescriptorError::InvalidThreshold {
+            threshold,
+            key_count: keys.len() as u64,
+        });
+    }
+
+    let multi = format!("sortedmulti({threshold},{})", keys.join(","));
+    let descriptor = match script_type {
+        Bip48ScriptType::P2wsh => format!("wsh({multi})"),
+        Bip48ScriptType::P2shP2wsh => format!("sh(wsh({multi}))"),
+    };
+    Descriptor::new(descriptor, network_kind)
+}
+
+/// Whether `descriptor` ends with `#` followed by its correct BIP-380 checksum. The keys and
+/// script are not parsed, so a descriptor can be checked for transcription errors on any network.
+#[uniffi::export]
+pub fn verify_descriptor_checksum(descriptor: String) -> bool {
+    match descriptor.rsplit_once('#') {
+        Some((body, checksum)) => calc_checksum(body).is_ok_and(|expected| expected == checksum),
+        None => false,
+    }
+}
+
+/// Parse `descriptor` and summarize it, for example to validate a pasted descriptor and report
+/// what it is before creating a wallet. Shorthand for `Descriptor::new` followed by
+/// `Descriptor::info`.
+#[uniffi::export]
+pub fn inspect_descriptor(
+    descriptor: String,
+    network_kind: NetworkKind,
+) -> Result<DescriptorInfo, DescriptorError> {
+    Descriptor::new(descriptor, network_kind).map(|descriptor| descriptor.info())
+}
@@ -1,23 +1,29 @@
-use crate::bitcoin::{BlockHash, Header, Transaction, Txid};
+use crate::bitcoin::{BlockHash, FeeRate, Header, Transaction, Txid};
 use crate::error::ElectrumError;
 use crate::types::KeychainKind;
 use crate::types::Update;
-use crate::types::{FullScanRequest, SyncRequest};
+use crate::types::{
+    wrap_full_scan_request, FullScanProgress, FullScanRequest, MerkleProof, ScanToken, SyncRequest,
+};
 
 use bdk_electrum::electrum_client::HeaderNotification as BdkHeaderNotification;
 use bdk_electrum::electrum_client::ServerFeaturesRes as BdkServerFeaturesRes;
 use bdk_electrum::BdkElectrumClient as BdkBdkElectrumClient;
+use bdk_wallet::bitcoin::hashes::Hash;
 use bdk_wallet::bitcoin::Transaction as BdkTransaction;
+use bdk_wallet::bitcoin::Txid as BdkTxid;
 use bdk_wallet::chain::spk_client::FullScanRequest as BdkFullScanRequest;
 use bdk_wallet::chain::spk_client::FullScanResponse as BdkFullScanResponse;
 use bdk_wallet::chain::spk_client::SyncRequest as BdkSyncRequest;
 use bdk_wallet::chain::spk_client::SyncResponse as BdkSyncResponse;
 use bdk_wallet::Update as BdkUpdate;
 
-use bdk_electrum::electrum_client::ElectrumApi;
+use bdk_electrum::electrum_client::{ElectrumApi, Param};
 use bdk_wallet::bitcoin::hex::{Case, DisplayHex};
+use bdk_wallet::serde_json;
 use std::collections::BTreeMap;
 use std::convert::TryFrom;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 /// Wrapper around an electrum_client::ElectrumApi which includes an internal in-memory transaction
@@ -25,19 +31,24 @@ use std::sync::Arc;
 #[derive(uniffi::Object)]
 pub struct ElectrumClient(BdkBdkElectrumClient<bdk_electrum::electrum_client::Client>);
 
-#[uniffi::export]
+#[uniffi::export(async_runtime = "tokio")]
 impl ElectrumClient {
     /// Creates a new bdk client from a electrum_client::ElectrumApi
-    /// Optional: Set the proxy of the builder
+    /// Optional: Set the proxy of the builder, e.g. a local Tor SOCKS5 endpoint like `127.0.0.1:9050`
     /// Optional: Set whether the server's TLS certificate is validated.
-    #[uniffi::constructor(default(socks5 = None, validate_domain = true))]
+    /// Optional: Set the socket timeout in seconds, which also applies when dialing through the proxy.
+    ///
+    /// Failing to reach the server, directly or through the proxy, is reported as an `ElectrumError`.
+    #[uniffi::constructor(default(socks5 = None, validate_domain = true, timeout = None))]
     pub fn new(
         url: String,
         socks5: Option<String>,
         validate_domain: bool,
+        timeout: Option<u8>,
     ) -> Result<Self, ElectrumError> {
         let mut config = bdk_electrum::electrum_client::ConfigBuilder::new();
         config = config.validate_domain(validate_domain);
+        config = config.timeout(timeout);
         if let Some(socks5) = socks5 {
             config = config.socks5(Some(bdk_electrum::electrum_client::Socks5Config::new(
                 socks5.as_str(),
@@ -64,13 +75,24 @@ impl ElectrumClient {
     ///   `Wallet.calculate_fee` and `Wallet.calculate_fee_rate` will return a
     ///   `CalculateFeeError::MissingTxOut` error if those TxOuts are not present in the transaction
     ///   graph.
+    /// - `progress`: optional callback reporting each script the scan reaches, see
+    ///   `FullScanProgress`. It is called from the thread running the scan.
+    /// - `token`: optional handle to cancel the scan, see `ScanToken`. A cancelled scan stops
+    ///   requesting scripts and returns `ElectrumError::Cancelled` once the batch in flight is
+    ///   answered. A scan that already had all its scripts is not cut short.
+    #[uniffi::method(default(progress = None, token = None))]
     pub fn full_scan(
         &self,
         request: Arc<FullScanRequest>,
         stop_gap: u64,
         batch_size: u64,
         fetch_prev_txouts: bool,
+        progress: Option<Arc<dyn FullScanProgress>>,
+        token: Option<Arc<ScanToken>>,
     ) -> Result<Arc<Update>, ElectrumError> {
+        if token.as_ref().is_some_and(|token| token.is_cancelled()) {
+            return Err(ElectrumError::Cancelled);
+        }
         // using option and take is not ideal but the only way to take full ownership of the request
         let request: BdkFullScanRequest<KeychainKind> = request
             .0
@@ -78,13 +100,18 @@ impl ElectrumClient {
             .unwrap()
             .take()
             .ok_or(ElectrumError::RequestAlreadyConsumed)?;
+        let (request, interrupted) = wrap_full_scan_request(request, stop_gap, progress, token);
 
-        let full_scan_result: BdkFullScanResponse<KeychainKind> = self.0.full_scan(
+        let full_scan_result = self.0.full_scan(
             request,
             stop_gap as usize,
             batch_size as usize,
             fetch_prev_txouts,
-        )?;
+        );
+        if interrupted.load(Ordering::SeqCst) {
+            return Err(ElectrumError::Cancelled);
+        }
+        let full_scan_result: BdkFullScanResponse<KeychainKind> = full_scan_result?;
 
         let update = BdkUpdate {
             last_active_indices: full_scan_result.last_active_indices,
@@ -108,14 +135,23 @@ impl ElectrumClient {
     ///   `CalculateFeeError::MissingTxOut` error if those TxOuts are not present in the transaction
     ///   graph.
     ///
+    /// - `token`: optional handle to cancel the sync, see `ScanToken`. With a token the sync runs
+    ///   one batch at a time and returns `ElectrumError::Cancelled` before the next batch once
+    ///   the token is cancelled. Each batch fetches the chain tip again.
+    ///
     /// If the scripts to sync are unknown, such as when restoring or importing a keychain that may
     /// include scripts that have been used, use full_scan with the keychain.
+    #[uniffi::method(default(token = None))]
     pub fn sync(
         &self,
         request: Arc<SyncRequest>,
         batch_size: u64,
         fetch_prev_txouts: bool,
+        token: Option<Arc<ScanToken>>,
     ) -> Result<Arc<Update>, ElectrumError> {
+        if token.as_ref().is_some_and(|token| token.is_cancelled()) {
+            return Err(ElectrumError::Cancelled);
+        }
         // using option and take is not ideal but the only way to take full ownership of the request
         let request: BdkSyncRequest<(KeychainKind, u32)> = request
             .0
@@ -124,9 +160,18 @@ impl ElectrumClient {
             .take()
             .ok_or(ElectrumError::RequestAlreadyConsumed)?;
 
-        let sync_result: BdkSyncResponse =
-            self.0
-                .sync(request, batch_size as usize, fetch_prev_txouts)?;
+        let sync_result: BdkSyncResponse = match &token {
+            Some(token) => token
+                .sync_in_chunks(request, batch_size as usize, |chunk| {
+                    self.0
+                        .sync(chunk, batch_size as usize, fetch_prev_txouts)
+                        .map_err(ElectrumError::from)
+                })?
+                .ok_or(ElectrumError::Cancelled)?,
+            None => self
+                .0
+                .sync(request, batch_size as usize, fetch_prev_txouts)?,
+        };
 
         let update = BdkUpdate {
             last_active_indices: BTreeMap::default(),
@@ -137,6 +182,44 @@ impl ElectrumClient {
         Ok(Arc::new(Update(update)))
     }
 
+    /// Async variant of `full_scan`. The scan runs on the blocking thread pool of a tokio runtime,
+    /// so awaiting it does not block the caller's executor.
+    #[uniffi::method(default(progress = None, token = None))]
+    pub async fn full_scan_async(
+        self: Arc<Self>,
+        request: Arc<FullScanRequest>,
+        stop_gap: u64,
+        batch_size: u64,
+        fetch_prev_txouts: bool,
+        progress: Option<Arc<dyn FullScanProgress>>,
+        token: Option<Arc<ScanToken>>,
+    ) -> Result<Arc<Update>, ElectrumError> {
+        run_blocking(move || {
+            self.full_scan(
+                request,
+                stop_gap,
+                batch_size,
+                fetch_prev_txouts,
+                progress,
+                token,
+            )
+        })
+        .await
+    }
+
+    /// Async variant of `sync`. The sync runs on the blocking thread pool of a tokio runtime, so
+    /// awaiting it does not block the caller's executor.
+    #[uniffi::method(default(token = None))]
+    pub async fn sync_async(
+        self: Arc<Self>,
+        request: Arc<SyncRequest>,
+        batch_size: u64,
+        fetch_prev_txouts: bool,
+        token: Option<Arc<ScanToken>>,
+    ) -> Result<Arc<Update>, ElectrumError> {
+        run_blocking(move || self.sync(request, batch_size, fetch_prev_txouts, token)).await
+    }
+
     /// Broadcasts a transaction to the network.
     pub fn transaction_broadcast(&self, tx: &Transaction) -> Result<Arc<Txid>, ElectrumError> {
         let bdk_transaction: BdkTransaction = tx.into();
@@ -165,12 +248,33 @@ impl ElectrumClient {
         ServerFeaturesRes::try_from(res)
     }
 
-    /// Estimates the fee required in bitcoin per kilobyte to confirm a transaction in `number` blocks.
-    pub fn estimate_fee(&self, number: u64) -> Result<f64, ElectrumError> {
-        self.0
+    /// Estimates the fee rate required to confirm a transaction within `number_of_blocks` blocks.
+    ///
+    /// The server answers in bitcoin per kilobyte, which is converted to a `FeeRate`. Servers
+    /// report a negative value when they cannot produce an estimate, which is returned as
+    /// `ElectrumError::FeeEstimateUnavailable`.
+    pub fn estimate_fee(&self, number_of_blocks: u64) -> Result<Arc<FeeRate>, ElectrumError> {
+        let btc_per_kvb = self
+            .0
             .inner
-            .estimate_fee(number as usize)
-            .map_err(ElectrumError::from)
+            .estimate_fee(number_of_blocks as usize)
+            .map_err(ElectrumError::from)?;
+        fee_rate_from_btc_per_kvb(btc_per_kvb)
+            .map(Arc::new)
+            .ok_or(ElectrumError::FeeEstimateUnavailable { number_of_blocks })
+    }
+
+    /// Returns the fee histogram of the server's mempool, from the highest fee rate to the lowest.
+    ///
+    /// Each entry gives the vsize of the transactions paying at least its fee rate and less than
+    /// the fee rate of the previous entry, which is enough to draw a mempool depth chart.
+    pub fn fee_histogram(&self) -> Result<Vec<FeeHistogramEntry>, ElectrumError> {
+        let histogram = self
+            .0
+            .inner
+            .raw_call("mempool.get_fee_histogram", Vec::<Param>::new())
+            .map_err(ElectrumError::from)?;
+        fee_histogram_from_json(&histogram)
     }
 
     /// Gets the block header for height `height`.
@@ -182,6 +286,16 @@ impl ElectrumClient {
             .map(Header::from)
     }
 
+    /// Gets the hash of the block at height `height`, computed from its header.
+    pub fn block_hash(&self, height: u64) -> Result<Arc<BlockHash>, ElectrumError> {
+        let header = self
+            .0
+            .inner
+            .block_header(height as usize)
+            .map_err(ElectrumError::from)?;
+        Ok(Arc::new(BlockHash(header.block_hash())))
+    }
+
     /// Subscribes to notifications for new block headers, by sending a blockchain.headers.subscribe call.
     pub fn block_headers_subscribe(&self) -> Result<HeaderNotification, ElectrumError> {
         self.0
@@ -211,6 +325,36 @@ impl ElectrumClient {
         self.0.inner.relay_fee().map_err(ElectrumError::from)
     }
 
+    /// Gets the merkle inclusion proof of the transaction `txid` confirmed at `height`.
+    ///
+    /// Check the proof against the merkle root of the block header at that height with
+    /// `TxMerkleNode::verify_merkle_proof`.
+    pub fn get_merkle_proof(
+        &self,
+        txid: Arc<Txid>,
+        height: u32,
+    ) -> Result<MerkleProof, ElectrumError> {
+        let res = self
+            .0
+            .inner
+            .transaction_get_merkle(&txid.0, height as usize)
+            .map_err(ElectrumError::from)?;
+
+        Ok(MerkleProof {
+            block_height: res.block_height as u32,
+            // the server sends the hashes in display order
+            merkle: res
+                .merkle
+                .into_iter()
+                .map(|mut hash| {
+                    hash.reverse();
+                    Arc::new(Txid(BdkTxid::from_byte_array(hash)))
+                })
+                .collect(),
+            pos: res.pos as u64,
+        })
+    }
+
     /// Gets the raw bytes of a transaction with txid. Returns an error if not found.
     pub fn transaction_get_raw(&self, txid: Arc<Txid>) -> Result<Vec<u8>, ElectrumError> {
         self.0
@@ -220,6 +364,82 @@ impl ElectrumClient {
     }
 }
 
+/// Run a blocking electrum call on the blocking thread pool of the tokio runtime and await the
+/// result. A panic in the call is reported as `ElectrumError::Panicked`.
+pub(crate) async fn run_blocking<T, F>(f: F) -> Result<T, ElectrumError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, ElectrumError> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(error) if error.is_panic() => {
+            let payload = error.into_panic();
+            let error_message = payload
+                .downcast_ref::<String>()
+                .map(String::as_str)
+                .or_else(|| payload.downcast_ref::<&str>().copied())
+                .unwrap_or("panic in electrum client")
+                .to_string();
+            Err(ElectrumError::Panicked { error_message })
+        }
+        // the task was cancelled because the runtime is shutting down
+        Err(_) => Err(ElectrumError::Mpsc),
+    }
+}
+
+/// Convert a fee estimate in bitcoin per kilobyte into a `FeeRate`, rejecting the negative and
+/// non-finite values servers use to signal that no estimate is available.
+pub(crate) fn fee_rate_from_btc_per_kvb(btc_per_kvb: f64) -> Option<FeeRate> {
+    if !btc_per_kvb.is_finite() || btc_per_kvb < 0.0 {
+        return None;
+    }
+    // 1 BTC/kvB = 100_000_000 sat per 4_000 wu
+    let sat_per_kwu = (btc_per_kvb * 25_000_000.0).round() as u64;
+    Some(FeeRate::from_sat_per_kwu(sat_per_kwu))
+}
+
+/// Parse the `[[fee_rate, vsize], ...]` pairs returned by `mempool.get_fee_histogram`, where the
+/// fee rate is in sat/vB.
+pub(crate) fn fee_histogram_from_json(
+    histogram: &serde_json::Value,
+) -> Result<Vec<FeeHistogramEntry>, ElectrumError> {
+    let invalid = || ElectrumError::InvalidResponse {
+        error_message: format!("invalid fee histogram returned by server: {histogram}"),
+    };
+    histogram
+        .as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|entry| {
+            let (fee_rate, vsize) = match entry.as_array().map(Vec::as_slice) {
+                Some([fee_rate, vsize]) => (fee_rate.as_f64(), vsize.as_u64()),
+                _ => return Err(invalid()),
+            };
+            match (fee_rate, vsize) {
+                (Some(fee_rate), Some(vsize)) if fee_rate.is_finite() && fee_rate >= 0.0 => {
+                    // 1 sat/vB = 250 sat/kwu
+                    let sat_per_kwu = (fee_rate * 250.0).round() as u64;
+                    Ok(FeeHistogramEntry {
+                        fee_rate: Arc::new(FeeRate::from_sat_per_kwu(sat_per_kwu)),
+                        vsize,
+                    })
+                }
+                _ => Err(invalid()),
+            }
+        })
+        .collect()
+}
+
+/// A bucket of the mempool fee histogram.
+#[derive(uniffi::Record)]
+pub struct FeeHistogramEntry {
+    /// The lowest fee rate of the transactions in the bucket.
+    pub fee_rate: Arc<FeeRate>,
+    /// The total vsize of the transactions in the bucket.
+    pub vsize: u64,
+}
+
 /// Response to an ElectrumClient.server_features request.
 #[derive(uniffi::Record)]
 pub struct ServerFeaturesRes {
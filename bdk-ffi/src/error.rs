@@ -1,3 +1,4 @@
+use crate::bitcoin::Txid;
 use crate::OutPoint;
 
 use bdk_electrum::electrum_client::Error as BdkElectrumError;
@@ -31,6 +32,7 @@ use bdk_wallet::LoadWithPersistError as BdkLoadWithPersistError;
 use bdk_wallet::{chain, CreateWithPersistError as BdkCreateWithPersistError};
 
 use std::convert::TryInto;
+use std::sync::Arc;
 
 // ------------------------------------------------------------------------
 // error definitions
@@ -83,6 +85,9 @@ pub enum AddressParseError {
     // This error is required because the bdk::bitcoin::address::ParseError is non-exhaustive
     #[error("other address parse error")]
     OtherAddressParseErr,
+
+    #[error("invalid bip21 uri: {error_message}")]
+    InvalidBip21Uri { error_message: String },
 }
 
 #[derive(Debug, thiserror::Error, uniffi::Error)]
@@ -154,6 +159,15 @@ pub enum CannotConnectError {
     Include { height: u32 },
 }
 
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum ChangeSetJsonError {
+    #[error("failed to serialize changeset: {error_message}")]
+    Serialize { error_message: String },
+
+    #[error("failed to deserialize changeset: {error_message}")]
+    Deserialize { error_message: String },
+}
+
 #[derive(Debug, thiserror::Error, uniffi::Error)]
 pub enum CreateTxError {
     #[error("descriptor error: {error_message}")]
@@ -221,6 +235,24 @@ pub enum CreateTxError {
 
     #[error("invalid lock time value")]
     LockTimeConversionError,
+
+    #[error("op_return data of {length} bytes exceeds the 80 byte limit")]
+    OpReturnDataTooLarge { length: u64 },
+
+    #[error("fee rate and absolute fee cannot both be set")]
+    FeePolicyConflict,
+
+    #[error("unsupported version {version}")]
+    UnsupportedVersion { version: i32 },
+
+    #[error("no output of the transaction pays to the script allowed to shrink")]
+    ShrinkOutputNotFound,
+
+    #[error("transaction not found: {txid}")]
+    TransactionNotFound { txid: String },
+
+    #[error("cannot calculate the fee of the transaction: {error_message}")]
+    CalculateFee { error_message: String },
 }
 
 #[derive(Debug, thiserror::Error, uniffi::Error)]
@@ -275,6 +307,21 @@ pub enum DescriptorError {
 
     #[error("external and internal descriptors are the same")]
     ExternalAndInternalAreTheSame,
+
+    #[error("the descriptor template requires a single extended key")]
+    InvalidKeyType,
+
+    #[error("invalid fingerprint: {error_message}")]
+    InvalidFingerprint { error_message: String },
+
+    #[error("invalid threshold {threshold} for {key_count} keys")]
+    InvalidThreshold { threshold: u64, key_count: u64 },
+
+    #[error("the descriptor is not ranged")]
+    NotRanged,
+
+    #[error("the key origin or derivation path does not match the BIP-48 account path")]
+    KeyOriginMismatch,
 }
 
 #[derive(Debug, thiserror::Error, uniffi::Error)]
@@ -344,6 +391,18 @@ pub enum ElectrumError {
 
     #[error("the request has already been consumed")]
     RequestAlreadyConsumed,
+
+    #[error("the server has no fee estimate for confirmation within {number_of_blocks} blocks")]
+    FeeEstimateUnavailable { number_of_blocks: u64 },
+
+    #[error("the scan was cancelled")]
+    Cancelled,
+
+    #[error("the electrum call panicked: {error_message}")]
+    Panicked { error_message: String },
+
+    #[error("the chain changed at height {height} while the sync was in progress")]
+    ChainChanged { height: u32 },
 }
 
 #[derive(Debug, thiserror::Error, uniffi::Error)]
@@ -372,6 +431,9 @@ pub enum EsploraError {
     #[error("transaction not found")]
     TransactionNotFound,
 
+    #[error("transactions not found: {txids:?}")]
+    TransactionsNotFound { txids: Vec<Arc<Txid>> },
+
     #[error("header height {height} not found")]
     HeaderHeightNotFound { height: u32 },
 
@@ -389,6 +451,15 @@ pub enum EsploraError {
 
     #[error("the server sent an invalid response")]
     InvalidResponse,
+
+    #[error("{parameter} must be greater than zero")]
+    ZeroScanParameter { parameter: String },
+
+    #[error("the scan was cancelled")]
+    Cancelled,
+
+    #[error("the chain changed at height {height} while the sync was in progress")]
+    ChainChanged { height: u32 },
 }
 
 #[derive(Debug, thiserror::Error, uniffi::Error)]
@@ -587,6 +658,18 @@ pub enum ParseAmountError {
 pub enum PersistenceError {
     #[error("persistence error: {error_message}")]
     Reason { error_message: String },
+
+    #[error(
+        "the {schema} schema is at version {version}, newer than the supported version {supported}"
+    )]
+    IncompatibleSchema {
+        schema: String,
+        version: u32,
+        supported: u32,
+    },
+
+    #[error("schema versions are only tracked by sqlite persisters")]
+    SqliteOnly,
 }
 
 #[derive(Debug, thiserror::Error, uniffi::Error)]
@@ -787,6 +870,27 @@ pub enum SignerError {
     Psbt { error_message: String },
 }
 
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum SilentPaymentAddressError {
+    #[error("invalid bech32m encoding: {error_message}")]
+    Bech32 { error_message: String },
+
+    #[error("unknown human-readable part {hrp}, expected sp or tsp")]
+    UnknownHrp { hrp: String },
+
+    #[error("address is not valid for the requested network")]
+    NetworkValidation,
+
+    #[error("unsupported silent payment address version {version}")]
+    UnsupportedVersion { version: u8 },
+
+    #[error("invalid payload length of {length} bytes")]
+    InvalidLength { length: u32 },
+
+    #[error("invalid public key: {error_message}")]
+    InvalidPublicKey { error_message: String },
+}
+
 #[derive(Debug, thiserror::Error, uniffi::Error)]
 pub enum TransactionError {
     #[error("io error")]
@@ -822,6 +926,21 @@ pub enum TxidParseError {
 pub enum CbfError {
     #[error("the node is no longer running")]
     NodeStopped,
+
+    #[error("invalid peer: {error_message}")]
+    InvalidPeer { error_message: String },
+}
+
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum LightClientBuilderError {
+    #[error("invalid host name: {hostname}")]
+    InvalidHostname { hostname: String },
+
+    #[error("could not resolve host name: {hostname}")]
+    UnresolvedHostname { hostname: String },
+
+    #[error("host name {hostname} cannot be resolved through the socks5 proxy")]
+    HostnameWithProxy { hostname: String },
 }
 
 // ------------------------------------------------------------------------
@@ -862,6 +981,14 @@ impl From<BdkAddForeignUtxoError> for AddForeignUtxoError {
     }
 }
 
+impl From<CannotConnectError> for ElectrumError {
+    fn from(error: CannotConnectError) -> Self {
+        match error {
+            CannotConnectError::Include { height } => ElectrumError::ChainChanged { height },
+        }
+    }
+}
+
 impl From<BdkElectrumError> for ElectrumError {
     fn from(error: BdkElectrumError) -> Self {
         match error {
@@ -1122,8 +1249,8 @@ impl From<BuildFeeBumpError> for CreateTxError {
             BuildFeeBumpError::UnknownUtxo(outpoint) => CreateTxError::UnknownUtxo {
                 outpoint: outpoint.to_string(),
             },
-            BuildFeeBumpError::TransactionNotFound(txid) => CreateTxError::UnknownUtxo {
-                outpoint: txid.to_string(),
+            BuildFeeBumpError::TransactionNotFound(txid) => CreateTxError::TransactionNotFound {
+                txid: txid.to_string(),
             },
             BuildFeeBumpError::TransactionConfirmed(txid) => CreateTxError::UnknownUtxo {
                 outpoint: txid.to_string(),
@@ -1131,8 +1258,8 @@ impl From<BuildFeeBumpError> for CreateTxError {
             BuildFeeBumpError::IrreplaceableTransaction(txid) => CreateTxError::UnknownUtxo {
                 outpoint: txid.to_string(),
             },
-            BuildFeeBumpError::FeeRateUnavailable => CreateTxError::FeeRateTooLow {
-                required: "unavailable".to_string(),
+            BuildFeeBumpError::FeeRateUnavailable => CreateTxError::CalculateFee {
+                error_message: error.to_string(),
             },
             BuildFeeBumpError::InvalidOutputIndex(outpoint) => CreateTxError::UnknownUtxo {
                 outpoint: outpoint.to_string(),
@@ -1240,6 +1367,14 @@ impl From<BdkEsploraError> for EsploraError {
     }
 }
 
+impl From<CannotConnectError> for EsploraError {
+    fn from(error: CannotConnectError) -> Self {
+        match error {
+            CannotConnectError::Include { height } => EsploraError::ChainChanged { height },
+        }
+    }
+}
+
 impl From<Box<BdkEsploraError>> for EsploraError {
     fn from(error: Box<BdkEsploraError>) -> Self {
         match *error {
@@ -1658,6 +1793,14 @@ pub enum HashParseError {
     InvalidHexString { hex: String },
 }
 
+impl From<LightClientBuilderError> for CbfError {
+    fn from(error: LightClientBuilderError) -> Self {
+        CbfError::InvalidPeer {
+            error_message: error.to_string(),
+        }
+    }
+}
+
 impl From<bdk_kyoto::bip157::ClientError> for CbfError {
     fn from(_value: bdk_kyoto::bip157::ClientError) -> Self {
         CbfError::NodeStopped
@@ -9,7 +9,9 @@ use crate::types::KeychainKind;
 use crate::types::Tx;
 use crate::types::TxStatus;
 use crate::types::Update;
-use crate::types::{FullScanRequest, MerkleProof, OutputStatus, SyncRequest};
+use crate::types::{
+    wrap_full_scan_request, FullScanRequest, MerkleProof, OutputStatus, ScanToken, SyncRequest,
+};
 
 use bdk_esplora::esplora_client::{BlockingClient, Builder};
 use bdk_esplora::EsploraExt;
@@ -21,6 +23,7 @@ use bdk_wallet::chain::spk_client::SyncResponse as BdkSyncResponse;
 use bdk_wallet::Update as BdkUpdate;
 
 use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 /// Wrapper around an esplora_client::BlockingClient which includes an internal in-memory transaction
@@ -32,12 +35,27 @@ pub struct EsploraClient(BlockingClient);
 impl EsploraClient {
     /// Creates a new bdk client from an esplora_client::BlockingClient.
     /// Optional: Set the proxy of the builder.
-    #[uniffi::constructor(default(proxy = None))]
-    pub fn new(url: String, proxy: Option<String>) -> Self {
+    /// Optional: Set the timeout of each request, in seconds.
+    /// Optional: Set how many times a request is retried, with exponential backoff, when the
+    /// server answers 429, 500 or 503. Other errors, including the remaining 4xx responses, fail
+    /// immediately. Defaults to 6 retries.
+    #[uniffi::constructor(default(proxy = None, timeout = None, max_retries = None))]
+    pub fn new(
+        url: String,
+        proxy: Option<String>,
+        timeout: Option<u64>,
+        max_retries: Option<u64>,
+    ) -> Self {
         let mut builder = Builder::new(url.as_str());
         if let Some(proxy) = proxy {
             builder = builder.proxy(proxy.as_str());
         }
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(max_retries) = max_retries {
+            builder = builder.max_retries(max_retries as usize);
+        }
         Self(builder.build_blocking())
     }
 
@@ -48,12 +66,27 @@ impl EsploraClient {
     /// (see [`FullScanRequest`]). The full scan for each keychain (`K`) stops after a gap of
     /// `stop_gap` script pubkeys with no associated transactions. `parallel_requests` specifies
     /// the maximum number of HTTP requests to make in parallel.
+    ///
+    /// Recovering a wallet that skipped many addresses requires a larger `stop_gap`. Both values
+    /// must be greater than zero, otherwise `EsploraError::ZeroScanParameter` is returned and the
+    /// request is left untouched.
+    ///
+    /// Cancelling the optional `token` stops the scan from requesting more scripts, and
+    /// `EsploraError::Cancelled` is returned once the requests in flight finish. A scan that
+    /// already had all its scripts is not cut short.
+    #[uniffi::method(default(token = None))]
     pub fn full_scan(
         &self,
         request: Arc<FullScanRequest>,
         stop_gap: u64,
         parallel_requests: u64,
+        token: Option<Arc<ScanToken>>,
     ) -> Result<Arc<Update>, EsploraError> {
+        check_scan_parameter("stop_gap", stop_gap)?;
+        check_scan_parameter("parallel_requests", parallel_requests)?;
+        if token.as_ref().is_some_and(|token| token.is_cancelled()) {
+            return Err(EsploraError::Cancelled);
+        }
         // using option and take is not ideal but the only way to take full ownership of the request
         let request: BdkFullScanRequest<KeychainKind> = request
             .0
@@ -61,22 +94,26 @@ impl EsploraClient {
             .unwrap()
             .take()
             .ok_or(EsploraError::RequestAlreadyConsumed)?;
+        let (request, interrupted) = wrap_full_scan_request(request, stop_gap, None, token);
 
-        let result: BdkFullScanResponse<KeychainKind> =
-            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                self.0
-                    .full_scan(request, stop_gap as usize, parallel_requests as usize)
-            }))
-            .map_err(|payload| {
-                let error_message = payload
-                    .downcast_ref::<String>()
-                    .map(String::as_str)
-                    .or_else(|| payload.downcast_ref::<&str>().copied())
-                    .unwrap_or("panic in esplora client")
-                    .to_string();
-
-                EsploraError::Parsing { error_message }
-            })??;
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            self.0
+                .full_scan(request, stop_gap as usize, parallel_requests as usize)
+        }))
+        .map_err(|payload| {
+            let error_message = payload
+                .downcast_ref::<String>()
+                .map(String::as_str)
+                .or_else(|| payload.downcast_ref::<&str>().copied())
+                .unwrap_or("panic in esplora client")
+                .to_string();
+
+            EsploraError::Parsing { error_message }
+        });
+        if interrupted.load(Ordering::SeqCst) {
+            return Err(EsploraError::Cancelled);
+        }
+        let result: BdkFullScanResponse<KeychainKind> = result??;
 
         let update = BdkUpdate {
             last_active_indices: result.last_active_indices,
@@ -91,12 +128,22 @@ impl EsploraClient {
     ///
     /// `request` provides the data required to perform a script-pubkey-based sync (see
     /// [`SyncRequest`]). `parallel_requests` specifies the maximum number of HTTP requests to make
-    /// in parallel.
+    /// in parallel and must be greater than zero.
+    ///
+    /// With the optional `token` the sync runs `parallel_requests` items at a time and returns
+    /// `EsploraError::Cancelled` before the next round of requests once the token is cancelled.
+    /// Each round fetches the chain tip again.
+    #[uniffi::method(default(token = None))]
     pub fn sync(
         &self,
         request: Arc<SyncRequest>,
         parallel_requests: u64,
+        token: Option<Arc<ScanToken>>,
     ) -> Result<Arc<Update>, EsploraError> {
+        check_scan_parameter("parallel_requests", parallel_requests)?;
+        if token.as_ref().is_some_and(|token| token.is_cancelled()) {
+            return Err(EsploraError::Cancelled);
+        }
         // using option and take is not ideal but the only way to take full ownership of the request
         let request: BdkSyncRequest<(KeychainKind, u32)> = request
             .0
@@ -105,20 +152,29 @@ impl EsploraClient {
             .take()
             .ok_or(EsploraError::RequestAlreadyConsumed)?;
 
-        let result: BdkSyncResponse =
-            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                self.0.sync(request, parallel_requests as usize)
-            }))
-            .map_err(|payload| {
-                let error_message = payload
-                    .downcast_ref::<String>()
-                    .map(String::as_str)
-                    .or_else(|| payload.downcast_ref::<&str>().copied())
-                    .unwrap_or("panic in esplora client")
-                    .to_string();
-
-                EsploraError::Parsing { error_message }
-            })??;
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| match &token {
+            Some(token) => token.sync_in_chunks(request, parallel_requests as usize, |chunk| {
+                self.0
+                    .sync(chunk, parallel_requests as usize)
+                    .map_err(EsploraError::from)
+            }),
+            None => self
+                .0
+                .sync(request, parallel_requests as usize)
+                .map(Some)
+                .map_err(EsploraError::from),
+        }))
+        .map_err(|payload| {
+            let error_message = payload
+                .downcast_ref::<String>()
+                .map(String::as_str)
+                .or_else(|| payload.downcast_ref::<&str>().copied())
+                .unwrap_or("panic in esplora client")
+                .to_string();
+
+            EsploraError::Parsing { error_message }
+        });
+        let result: BdkSyncResponse = result??.ok_or(EsploraError::Cancelled)?;
 
         let update = BdkUpdate {
             last_active_indices: BTreeMap::default(),
@@ -130,19 +186,65 @@ impl EsploraClient {
     }
 
     /// Broadcast a [`Transaction`] to Esplora.
-    pub fn broadcast(&self, transaction: &Transaction) -> Result<(), EsploraError> {
+    ///
+    /// Returns the [`Txid`] of the broadcast transaction so it can be tracked right away.
+    pub fn broadcast(&self, transaction: &Transaction) -> Result<Arc<Txid>, EsploraError> {
         let bdk_transaction: BdkTransaction = transaction.into();
         self.0
             .broadcast(&bdk_transaction)
-            .map_err(EsploraError::from)
+            .map_err(EsploraError::from)?;
+        Ok(Arc::new(Txid(bdk_transaction.compute_txid())))
     }
 
     /// Get a [`Transaction`] option given its [`Txid`].
+    ///
+    /// Returns `None` if the server does not know the transaction (HTTP 404). Any other failure,
+    /// such as the server being unreachable, is returned as an [`EsploraError`].
     pub fn get_tx(&self, txid: Arc<Txid>) -> Result<Option<Arc<Transaction>>, EsploraError> {
         let tx_opt = self.0.get_tx(&txid.0)?;
         Ok(tx_opt.map(|inner| Arc::new(Transaction::from(inner))))
     }
 
+    /// Get the [`Transaction`]s for `txids`, in the same order, making up to `parallel_requests`
+    /// HTTP requests at a time.
+    ///
+    /// If the server does not know some of the transactions, they are all listed in
+    /// `EsploraError::TransactionsNotFound`. `parallel_requests` must be greater than zero.
+    pub fn get_txs(
+        &self,
+        txids: Vec<Arc<Txid>>,
+        parallel_requests: u64,
+    ) -> Result<Vec<Arc<Transaction>>, EsploraError> {
+        check_scan_parameter("parallel_requests", parallel_requests)?;
+        let mut transactions = Vec::with_capacity(txids.len());
+        let mut missing = Vec::new();
+        for chunk in txids.chunks(parallel_requests as usize) {
+            let results = std::thread::scope(|scope| {
+                let handles: Vec<_> = chunk
+                    .iter()
+                    .map(|txid| scope.spawn(move || self.0.get_tx(&txid.0)))
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join())
+                    .collect::<Vec<_>>()
+            });
+            for (txid, result) in chunk.iter().zip(results) {
+                let tx = result.map_err(|_| EsploraError::Parsing {
+                    error_message: "panic in esplora client".to_string(),
+                })??;
+                match tx {
+                    Some(tx) => transactions.push(Arc::new(Transaction::from(tx))),
+                    None => missing.push(txid.clone()),
+                }
+            }
+        }
+        if !missing.is_empty() {
+            return Err(EsploraError::TransactionsNotFound { txids: missing });
+        }
+        Ok(transactions)
+    }
+
     /// Get a `Transaction` given its `Txid`.
     pub fn get_tx_no_opt(&self, txid: Arc<Txid>) -> Result<Arc<Transaction>, EsploraError> {
         self.0
@@ -153,6 +255,8 @@ impl EsploraClient {
     }
 
     /// Get the height of the current blockchain tip.
+    ///
+    /// A transaction confirmed at height `h` has `tip_height - h + 1` confirmations.
     pub fn get_height(&self) -> Result<u32, EsploraError> {
         self.0.get_height().map_err(EsploraError::from)
     }
@@ -183,10 +287,10 @@ impl EsploraClient {
     pub fn get_block_by_hash(
         &self,
         block_hash: Arc<BlockHash>,
-    ) -> Result<Option<Block>, EsploraError> {
+    ) -> Result<Option<Arc<Block>>, EsploraError> {
         self.0
             .get_block_by_hash(&block_hash.0)
-            .map(|block| block.map(|block| block.into()))
+            .map(|block| block.map(|block| Arc::new(block.into())))
             .map_err(EsploraError::from)
     }
 
@@ -263,3 +367,12 @@ impl EsploraClient {
             .map_err(EsploraError::from)
     }
 }
+
+fn check_scan_parameter(parameter: &str, value: u64) -> Result<(), EsploraError> {
+    if value == 0 {
+        return Err(EsploraError::ZeroScanParameter {
+            parameter: parameter.to_string(),
+        });
+    }
+    Ok(())
+}
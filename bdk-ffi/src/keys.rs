@@ -1,9 +1,10 @@
 use crate::bitcoin::{ChildNumber, NetworkKind};
-use crate::error::{Bip32Error, Bip39Error, DescriptorKeyError};
+use crate::error::{Bip32Error, Bip39Error, DescriptorError, DescriptorKeyError};
 use crate::{impl_from_core_type, impl_into_core_type};
 
 use bdk_wallet::bitcoin::bip32::ChildNumber as BdkChildNumber;
 use bdk_wallet::bitcoin::bip32::DerivationPath as BdkDerivationPath;
+use bdk_wallet::bitcoin::bip32::Fingerprint;
 use bdk_wallet::bitcoin::key::Secp256k1;
 use bdk_wallet::bitcoin::secp256k1::rand;
 use bdk_wallet::bitcoin::secp256k1::rand::Rng;
@@ -16,7 +17,7 @@ use bdk_wallet::keys::{
 use bdk_wallet::miniscript::descriptor::{DescriptorXKey, Wildcard};
 use bdk_wallet::miniscript::BareCtx;
 
-use crate::types::WildcardType;
+use crate::types::{Bip48ScriptType, KeychainKind, WildcardType};
 use std::convert::TryFrom;
 use std::fmt::Display;
 use std::str::FromStr;
@@ -59,6 +60,12 @@ impl Mnemonic {
             .map(Mnemonic)
             .map_err(Bip39Error::from)
     }
+
+    /// Derive the 64-byte BIP-39 seed, using the passphrase as the optional "25th word". Use an
+    /// empty passphrase if none was set.
+    pub fn to_seed(&self, passphrase: String) -> Vec<u8> {
+        self.0.to_seed(passphrase).to_vec()
+    }
 }
 
 impl Display for Mnemonic {
@@ -154,6 +161,50 @@ impl DescriptorSecretKey {
         Self(descriptor_secret_key)
     }
 
+    /// Multisig account key as defined in BIP-48: https://github.com/bitcoin/bips/blob/master/bip-0048.mediawiki
+    ///
+    /// Derives `m/48'/coin_type'/account'/script_type'` from the master key and returns the
+    /// account key with its origin, extended by the keychain and an unhardened wildcard. The
+    /// resulting key can be combined with the keys of other cosigners in a `sortedmulti`
+    /// descriptor. The `secret_key` must be a master key, without an origin or derivation path.
+    #[uniffi::constructor]
+    pub fn new_bip48(
+        secret_key: &DescriptorSecretKey,
+        keychain_kind: KeychainKind,
+        account: u32,
+        script_type: Bip48ScriptType,
+        network_kind: NetworkKind,
+    ) -> Result<Self, DescriptorError> {
+        let secp = Secp256k1::new();
+        let derivable_key = &secret_key.0;
+
+        match derivable_key {
+            BdkDescriptorSecretKey::Single(_) => Err(DescriptorError::InvalidKeyType),
+            BdkDescriptorSecretKey::XPrv(descriptor_x_key) => {
+                if descriptor_x_key.origin.is_some()
+                    || !descriptor_x_key.derivation_path.is_master()
+                {
+                    return Err(DescriptorError::KeyOriginMismatch);
+                }
+                let account_path = bip48_account_path(account, script_type, network_kind)?;
+                let master_key = descriptor_x_key.xkey;
+                let account_key =
+                    master_key
+                        .derive_priv(&secp, &account_path)
+                        .map_err(|error| DescriptorError::Bip32 {
+                            error_message: error.to_string(),
+                        })?;
+                Ok(Self(BdkDescriptorSecretKey::XPrv(DescriptorXKey {
+                    origin: Some((master_key.fingerprint(&secp), account_path)),
+                    xkey: account_key,
+                    derivation_path: bip48_keychain_path(keychain_kind),
+                    wildcard: Wildcard::Unhardened,
+                })))
+            }
+            BdkDescriptorSecretKey::MultiXPrv(_) => Err(DescriptorError::InvalidKeyType),
+        }
+    }
+
     /// Attempt to parse a string as a descriptor secret key.
     #[uniffi::constructor]
     pub fn from_string(private_key: String) -> Result<Self, DescriptorKeyError> {
@@ -294,6 +345,54 @@ pub struct DescriptorPublicKey(pub(crate) BdkDescriptorPublicKey);
 
 #[uniffi::export]
 impl DescriptorPublicKey {
+    /// Multisig account key as defined in BIP-48: https://github.com/bitcoin/bips/blob/master/bip-0048.mediawiki
+    ///
+    /// The `public_key` must be the account-level extended public key found at
+    /// `m/48'/coin_type'/account'/script_type'`, and `fingerprint` the fingerprint of the master
+    /// key it was derived from. The returned key carries that origin and is extended by the
+    /// keychain and an unhardened wildcard. A key that already has an origin must have this one,
+    /// and a key with a derivation path is rejected.
+    #[uniffi::constructor]
+    pub fn new_bip48_public(
+        public_key: &DescriptorPublicKey,
+        fingerprint: String,
+        keychain_kind: KeychainKind,
+        account: u32,
+        script_type: Bip48ScriptType,
+        network_kind: NetworkKind,
+    ) -> Result<Self, DescriptorError> {
+        let fingerprint = Fingerprint::from_str(fingerprint.as_str()).map_err(|error| {
+            DescriptorError::InvalidFingerprint {
+                error_message: error.to_string(),
+            }
+        })?;
+        let derivable_key = &public_key.0;
+
+        match derivable_key {
+            BdkDescriptorPublicKey::Single(_) => Err(DescriptorError::InvalidKeyType),
+            BdkDescriptorPublicKey::XPub(descriptor_x_key) => {
+                let origin = (
+                    fingerprint,
+                    bip48_account_path(account, script_type, network_kind)?,
+                );
+                let origin_conflicts = descriptor_x_key
+                    .origin
+                    .as_ref()
+                    .is_some_and(|key_origin| *key_origin != origin);
+                if origin_conflicts || !descriptor_x_key.derivation_path.is_master() {
+                    return Err(DescriptorError::KeyOriginMismatch);
+                }
+                Ok(Self(BdkDescriptorPublicKey::XPub(DescriptorXKey {
+                    origin: Some(origin),
+                    xkey: descriptor_x_key.xkey,
+                    derivation_path: bip48_keychain_path(keychain_kind),
+                    wildcard: Wildcard::Unhardened,
+                })))
+            }
+            BdkDescriptorPublicKey::MultiXPub(_) => Err(DescriptorError::InvalidKeyType),
+        }
+    }
+
     /// Attempt to parse a string as a descriptor public key.
     #[uniffi::constructor]
     pub fn from_string(public_key: String) -> Result<Self, DescriptorKeyError> {
@@ -397,6 +496,14 @@ impl DescriptorPublicKey {
     pub fn master_fingerprint(&self) -> String {
         self.0.master_fingerprint().to_string()
     }
+
+    /// The full derivation path from the master key, including the origin. `None` if the key has
+    /// multiple derivation paths.
+    pub fn derivation_path(&self) -> Option<Arc<DerivationPath>> {
+        self.0
+            .full_derivation_path()
+            .map(|path| Arc::new(DerivationPath(path)))
+    }
 }
 
 impl Display for DescriptorPublicKey {
@@ -404,3 +511,54 @@ impl Display for DescriptorPublicKey {
         self.0.fmt(f)
     }
 }
+
+fn bip48_account_path(
+    account: u32,
+    script_type: Bip48ScriptType,
+    network_kind: NetworkKind,
+) -> Result<BdkDerivationPath, DescriptorError> {
+    let coin_type = if network_kind.is_mainnet() { 0 } else { 1 };
+    let script_type = match script_type {
+        Bip48ScriptType::P2shP2wsh => 1,
+        Bip48ScriptType::P2wsh => 2,
+    };
+    [48, coin_type, account, script_type]
+        .iter()
+        .map(|index| BdkChildNumber::from_hardened_idx(*index))
+        .collect::<Result<Vec<BdkChildNumber>, _>>()
+        .map(BdkDerivationPath::from)
+        .map_err(|error| DescriptorError::Bip32 {
+            error_message: error.to_string(),
+        })
+}
+
+/// Check that `key` is an extended public key whose origin is a BIP-48 account path of
+/// `script_type`.
+pub(crate) fn check_bip48_key(
+    key: &BdkDescriptorPublicKey,
+    script_type: Bip48ScriptType,
+    network_kind: NetworkKind,
+) -> Result<(), DescriptorError> {
+    let BdkDescriptorPublicKey::XPub(descriptor_x_key) = key else {
+        return Err(DescriptorError::InvalidKeyType);
+    };
+    // the account is the only index allowed to differ between cosigners
+    let expected = bip48_account_path(0, script_type, network_kind)?;
+    let expected: &[BdkChildNumber] = expected.as_ref();
+    let is_bip48 = descriptor_x_key.origin.as_ref().is_some_and(|(_, path)| {
+        let path: &[BdkChildNumber] = path.as_ref();
+        path.len() == 4 && path[..2] == expected[..2] && path[3] == expected[3]
+    });
+    if !is_bip48 {
+        return Err(DescriptorError::KeyOriginMismatch);
+    }
+    Ok(())
+}
+
+fn bip48_keychain_path(keychain_kind: KeychainKind) -> BdkDerivationPath {
+    let index = match keychain_kind {
+        KeychainKind::External => 0,
+        KeychainKind::Internal => 1,
+    };
+    BdkDerivationPath::from(vec![BdkChildNumber::Normal { index }])
+}
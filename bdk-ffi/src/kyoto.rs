@@ -15,17 +15,21 @@ use bdk_kyoto::UnboundedReceiver;
 use bdk_kyoto::UpdateSubscriber;
 use bdk_kyoto::Warning as Warn;
 
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::task::Poll;
 use std::time::Duration;
 
 use tokio::sync::Mutex;
+use tokio::sync::Notify;
 
 use crate::bitcoin::BlockHash;
 use crate::bitcoin::Transaction;
 use crate::bitcoin::Wtxid;
 use crate::error::CbfError;
+use crate::error::LightClientBuilderError;
 use crate::types::BlockId;
 use crate::types::Update;
 use crate::wallet::Wallet;
@@ -52,6 +56,9 @@ pub struct CbfClient {
     info_rx: Mutex<Receiver<bdk_kyoto::Info>>,
     warning_rx: Mutex<UnboundedReceiver<bdk_kyoto::Warning>>,
     update_rx: Mutex<UpdateSubscriber<bdk_kyoto::wallets::Single>>,
+    state: std::sync::Mutex<NodeState>,
+    stop_update: Notify,
+    socks5_proxy: Option<Socks5Proxy>,
 }
 
 /// A [`CbfNode`] gathers transactions for a [`Wallet`].
@@ -102,7 +109,7 @@ pub struct CbfBuilder {
     data_dir: Option<String>,
     scan_type: ScanType,
     socks5_proxy: Option<Socks5Proxy>,
-    peers: Vec<Peer>,
+    peers: Vec<Arc<Peer>>,
 }
 
 #[allow(clippy::new_without_default)]
@@ -148,7 +155,7 @@ impl CbfBuilder {
     }
 
     /// Bitcoin full-nodes to attempt a connection with.
-    pub fn peers(&self, peers: Vec<Peer>) -> Arc<Self> {
+    pub fn peers(&self, peers: Vec<Arc<Peer>>) -> Arc<Self> {
         Arc::new(CbfBuilder {
             peers,
             ..self.clone()
@@ -177,14 +184,19 @@ impl CbfBuilder {
     }
 
     /// Construct a [`CbfComponents`] for a [`Wallet`].
-    pub fn build(&self, wallet: &Wallet) -> CbfComponents {
+    ///
+    /// Peers built with [`Peer::from_hostname`] are resolved here. A name that does not resolve
+    /// returns `LightClientBuilderError::UnresolvedHostname`. With a socks5 proxy configured the
+    /// lookup would leak outside the proxy, so `LightClientBuilderError::HostnameWithProxy` is
+    /// returned instead.
+    pub fn build(&self, wallet: &Wallet) -> Result<CbfComponents, LightClientBuilderError> {
+        let trusted_peers = self
+            .peers
+            .iter()
+            .map(|peer| peer.to_trusted_peer(self.socks5_proxy.as_ref()))
+            .collect::<Result<Vec<TrustedPeer>, _>>()?;
         let wallet = wallet.get_wallet();
 
-        let mut trusted_peers = Vec::new();
-        for peer in self.peers.iter() {
-            trusted_peers.push(peer.clone().into());
-        }
-
         let scan_type = match self.scan_type.clone() {
             ScanType::Sync => bdk_kyoto::ScanType::Sync,
             ScanType::Recovery {
@@ -256,12 +268,15 @@ impl CbfBuilder {
             info_rx: Mutex::new(logging.info_subscriber),
             warning_rx: Mutex::new(logging.warning_subscriber),
             update_rx: Mutex::new(update_subscriber),
+            state: std::sync::Mutex::new(NodeState::Connecting),
+            stop_update: Notify::new(),
+            socks5_proxy: self.socks5_proxy.clone(),
         };
 
-        CbfComponents {
+        Ok(CbfComponents {
             client: Arc::new(client),
             node: Arc::new(node),
-        }
+        })
     }
 }
 
@@ -270,37 +285,83 @@ impl CbfClient {
     /// Return the next available info message from a node. If none is returned, the node has stopped.
     pub async fn next_info(&self) -> Result<Info, CbfError> {
         let mut info_rx = self.info_rx.lock().await;
-        info_rx
+        let info: Info = info_rx
             .recv()
             .await
             .map(|e| e.into())
-            .ok_or(CbfError::NodeStopped)
+            .ok_or(CbfError::NodeStopped)?;
+        self.state.lock().unwrap().apply_info(&info);
+        Ok(info)
     }
 
     /// Return the next available warning message from a node. If none is returned, the node has stopped.
     pub async fn next_warning(&self) -> Result<Warning, CbfError> {
         let mut warn_rx = self.warning_rx.lock().await;
-        warn_rx
+        let warning: Warning = warn_rx
             .recv()
             .await
             .map(|warn| warn.into())
-            .ok_or(CbfError::NodeStopped)
+            .ok_or(CbfError::NodeStopped)?;
+        self.state.lock().unwrap().apply_warning(&warning);
+        Ok(warning)
     }
 
     /// Return an [`Update`]. This is method returns once the node syncs to the rest of
     /// the network or a new block has been gossiped.
-    pub async fn update(&self) -> Result<Update, CbfError> {
-        let update = self
-            .update_rx
-            .lock()
-            .await
-            .update()
+    ///
+    /// A pending call returns `None` when [`CbfClient::request_stop_update`] is called. The node
+    /// keeps running, and the next call resumes waiting for an update.
+    pub async fn update(&self) -> Result<Option<Arc<Update>>, CbfError> {
+        let cancelled = self.stop_update.notified();
+        let mut cancelled = std::pin::pin!(cancelled);
+        let mut update_rx = self.update_rx.lock().await;
+        let mut update = std::pin::pin!(update_rx.update());
+        let update = std::future::poll_fn(|cx| {
+            if cancelled.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Ok(None));
+            }
+            update
+                .as_mut()
+                .poll(cx)
+                .map(|update| update.map(Some).map_err(|_| CbfError::NodeStopped))
+        })
+        .await?;
+        let Some(update) = update else {
+            return Ok(None);
+        };
+        *self.state.lock().unwrap() = NodeState::Synced;
+        Ok(Some(Arc::new(Update(update))))
+    }
+
+    /// Cancel the pending call to [`CbfClient::update`] without stopping the node. If no update
+    /// is being awaited, the next call to [`CbfClient::update`] is cancelled instead.
+    pub fn request_stop_update(&self) {
+        self.stop_update.notify_one();
+    }
+
+    /// The sync state of the node, derived from the messages and updates received so far.
+    /// The state only advances while [`CbfClient::next_info`], [`CbfClient::next_warning`] and
+    /// [`CbfClient::update`] are being polled.
+    pub fn node_state(&self) -> NodeState {
+        *self.state.lock().unwrap()
+    }
+
+    /// The number of peers the node is currently connected to.
+    pub async fn connected_peers(&self) -> Result<u32, CbfError> {
+        let peers = self
+            .sender
+            .peer_info()
             .await
             .map_err(|_| CbfError::NodeStopped)?;
-        Ok(Update(update))
+        Ok(peers.len() as u32)
     }
 
     /// Broadcast a transaction to the network, erroring if the node has stopped running.
+    ///
+    /// The returned [`Wtxid`] confirms a connected peer requested the transaction. A peer that
+    /// rejects it, for instance for paying too little fee, is reported as
+    /// [`Warning::TransactionRejected`]. Compare the fee rate against
+    /// [`CbfClient::min_broadcast_feerate`] before broadcasting fee-critical transactions.
     pub async fn broadcast(&self, transaction: &Transaction) -> Result<Arc<Wtxid>, CbfError> {
         let tx = transaction.into();
         self.sender
@@ -333,8 +394,11 @@ impl CbfClient {
         Ok(Arc::new(fee_rate.into()))
     }
 
-    /// Add another [`Peer`] to attempt a connection with.
-    pub fn connect(&self, peer: Peer) -> Result<(), CbfError> {
+    /// Add another [`Peer`] to attempt a connection with. A peer built from a host name is
+    /// resolved first, as in [`CbfBuilder::build`], and failures are returned as
+    /// [`CbfError::InvalidPeer`].
+    pub fn connect(&self, peer: Arc<Peer>) -> Result<(), CbfError> {
+        let peer = peer.to_trusted_peer(self.socks5_proxy.as_ref())?;
         self.sender
             .add_peer(peer)
             .map_err(|_| CbfError::NodeStopped)
@@ -421,6 +485,47 @@ impl From<bdk_kyoto::Info> for Info {
     }
 }
 
+/// The sync progress of a [`CbfNode`], as observed by the [`CbfClient`].
+#[derive(Debug, Clone, Copy, PartialEq, uniffi::Enum)]
+pub enum NodeState {
+    /// The node is looking for connections to peers.
+    Connecting,
+    /// The required connections are met and block headers and filters are being downloaded.
+    SyncingFilters {
+        /// The percent of filters downloaded.
+        filters_downloaded_percent: f32,
+    },
+    /// The node has caught up to the rest of the network and produced an [`Update`].
+    Synced,
+}
+
+impl NodeState {
+    pub(crate) fn apply_info(&mut self, info: &Info) {
+        match info {
+            Info::ConnectionsMet if *self == NodeState::Connecting => {
+                *self = NodeState::SyncingFilters {
+                    filters_downloaded_percent: 0.0,
+                }
+            }
+            Info::Progress {
+                filters_downloaded_percent,
+                ..
+            } if *self != NodeState::Synced => {
+                *self = NodeState::SyncingFilters {
+                    filters_downloaded_percent: *filters_downloaded_percent,
+                }
+            }
+            _ => (),
+        }
+    }
+
+    pub(crate) fn apply_warning(&mut self, warning: &Warning) {
+        if let Warning::NeedConnections = warning {
+            *self = NodeState::Connecting;
+        }
+    }
+}
+
 /// Warnings a node may issue while running.
 #[derive(Debug, uniffi::Enum)]
 pub enum Warning {
@@ -504,15 +609,80 @@ pub enum RecoveryPoint {
 }
 
 /// A peer to connect to over the Bitcoin peer-to-peer network.
-#[derive(Clone, uniffi::Record)]
+#[derive(Debug, Clone, uniffi::Object)]
 pub struct Peer {
-    /// The IP address to reach the node.
-    pub address: Arc<IpAddress>,
-    /// The port to reach the node. If none is provided, the default
-    /// port for the selected network will be used.
-    pub port: Option<u16>,
-    /// Does the remote node offer encrypted peer-to-peer connection.
-    pub v2_transport: bool,
+    address: PeerAddress,
+    port: Option<u16>,
+    v2_transport: bool,
+}
+
+#[derive(Debug, Clone)]
+enum PeerAddress {
+    Ip(IpAddr),
+    Domain(String),
+}
+
+#[uniffi::export]
+impl Peer {
+    /// A peer reached at an IP address.
+    ///
+    /// * `port`: The port to reach the node. If none is provided, the default port for the
+    ///   selected network will be used.
+    /// * `v2_transport`: Does the remote node offer encrypted peer-to-peer connection.
+    #[uniffi::constructor]
+    pub fn new(address: Arc<IpAddress>, port: Option<u16>, v2_transport: bool) -> Self {
+        Peer {
+            address: PeerAddress::Ip(address.inner),
+            port,
+            v2_transport,
+        }
+    }
+
+    /// A peer reached by host name, like `node.example.com`. The name is kept as given and only
+    /// resolved by [`CbfBuilder::build`]. Returns `LightClientBuilderError::InvalidHostname` if
+    /// the name is not a valid DNS host name.
+    ///
+    /// The name is resolved with a blocking lookup through the system resolver, so
+    /// [`CbfBuilder::build`] and [`CbfClient::connect`] block the calling thread until it
+    /// completes. Call them off the main thread of an app.
+    #[uniffi::constructor]
+    pub fn from_hostname(
+        hostname: String,
+        port: Option<u16>,
+        v2_transport: bool,
+    ) -> Result<Self, LightClientBuilderError> {
+        if !is_valid_hostname(&hostname) {
+            return Err(LightClientBuilderError::InvalidHostname { hostname });
+        }
+        Ok(Peer {
+            address: PeerAddress::Domain(hostname),
+            port,
+            v2_transport,
+        })
+    }
+
+    /// The host name of the peer, if it was built from one.
+    pub fn hostname(&self) -> Option<String> {
+        match &self.address {
+            PeerAddress::Ip(_) => None,
+            PeerAddress::Domain(hostname) => Some(hostname.clone()),
+        }
+    }
+}
+
+/// Labels of letters, digits and inner hyphens, each 1 to 63 characters, at most 253 characters
+/// in total.
+fn is_valid_hostname(hostname: &str) -> bool {
+    let hostname = hostname.strip_suffix('.').unwrap_or(hostname);
+    !hostname.is_empty()
+        && hostname.len() <= 253
+        && hostname.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
 }
 
 /// An IP address to connect to over TCP.
@@ -558,9 +728,14 @@ pub struct Socks5Proxy {
     pub port: u16,
 }
 
-impl From<Peer> for TrustedPeer {
-    fn from(peer: Peer) -> Self {
-        let services = if peer.v2_transport {
+impl Peer {
+    /// The peer as the node expects it. A host name is resolved with the system resolver, which
+    /// would bypass a socks5 proxy, so host names are rejected when one is configured.
+    pub(crate) fn to_trusted_peer(
+        &self,
+        socks5_proxy: Option<&Socks5Proxy>,
+    ) -> Result<TrustedPeer, LightClientBuilderError> {
+        let services = if self.v2_transport {
             let mut services = ServiceFlags::P2P_V2;
             services.add(ServiceFlags::NETWORK);
             services.add(ServiceFlags::COMPACT_FILTERS);
@@ -570,11 +745,29 @@ impl From<Peer> for TrustedPeer {
             services.add(ServiceFlags::NETWORK);
             services
         };
-        let addr_v2 = match peer.address.inner {
+        let ip = match &self.address {
+            PeerAddress::Ip(ip) => *ip,
+            PeerAddress::Domain(hostname) => {
+                if socks5_proxy.is_some() {
+                    return Err(LightClientBuilderError::HostnameWithProxy {
+                        hostname: hostname.clone(),
+                    });
+                }
+                (hostname.as_str(), self.port.unwrap_or_default())
+                    .to_socket_addrs()
+                    .ok()
+                    .and_then(|mut addrs| addrs.next())
+                    .map(|addr| addr.ip())
+                    .ok_or_else(|| LightClientBuilderError::UnresolvedHostname {
+                        hostname: hostname.clone(),
+                    })?
+            }
+        };
+        let addr_v2 = match ip {
             IpAddr::V4(ipv4_addr) => AddrV2::Ipv4(ipv4_addr),
             IpAddr::V6(ipv6_addr) => AddrV2::Ipv6(ipv6_addr),
         };
-        TrustedPeer::new(addr_v2, peer.port, services)
+        Ok(TrustedPeer::new(addr_v2, self.port, services))
     }
 }
 
@@ -7,8 +7,9 @@ use bdk_wallet::migration::{
 };
 use bdk_wallet::{rusqlite::Connection as BdkConnection, WalletPersister};
 
+use std::collections::HashMap;
 use std::ops::DerefMut;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
 
 /// Definition of a wallet persistence implementation.
 #[uniffi::export(with_foreign)]
@@ -20,6 +21,19 @@ pub trait Persistence: Send + Sync {
     fn persist(&self, changeset: Arc<ChangeSet>) -> Result<(), PersistenceError>;
 }
 
+/// Hands a fixed `ChangeSet` to the wallet on load and ignores any later changes.
+pub(crate) struct ChangeSetSnapshot(pub(crate) Arc<ChangeSet>);
+
+impl Persistence for ChangeSetSnapshot {
+    fn initialize(&self) -> Result<Arc<ChangeSet>, PersistenceError> {
+        Ok(self.0.clone())
+    }
+
+    fn persist(&self, _changeset: Arc<ChangeSet>) -> Result<(), PersistenceError> {
+        Ok(())
+    }
+}
+
 pub(crate) enum PersistenceType {
     Custom(Arc<dyn Persistence>),
     Sql(Mutex<BdkConnection>),
@@ -47,9 +61,23 @@ pub struct Persister {
 #[uniffi::export]
 impl Persister {
     /// Create a new Sqlite connection at the specified file path.
+    ///
+    /// Returns `PersistenceError::IncompatibleSchema` if the file was written by a newer version
+    /// of the library, whose tables this version cannot safely read or write.
     #[uniffi::constructor]
     pub fn new_sqlite(path: String) -> Result<Self, PersistenceError> {
         let conn = BdkConnection::open(path)?;
+        let supported = supported_schema_versions()?;
+        for (schema, version) in stored_schema_versions(&conn)? {
+            let supported = supported.get(&schema).copied().unwrap_or(0);
+            if version > supported {
+                return Err(PersistenceError::IncompatibleSchema {
+                    schema,
+                    version,
+                    supported,
+                });
+            }
+        }
         Ok(Self {
             inner: PersistenceType::Sql(conn.into()).into(),
         })
@@ -72,6 +100,60 @@ impl Persister {
         }
     }
 
+    /// The version of the wallet schema in the Sqlite store, or `None` if no wallet was persisted
+    /// to it yet.
+    pub fn schema_version(&self) -> Result<Option<u32>, PersistenceError> {
+        let lock = self.inner.lock().unwrap();
+        match &*lock {
+            PersistenceType::Sql(conn) => {
+                let conn = conn.lock().unwrap();
+                Ok(stored_schema_versions(&conn)?
+                    .get(WALLET_SCHEMA_NAME)
+                    .copied())
+            }
+            PersistenceType::Custom(_) => Err(PersistenceError::SqliteOnly),
+        }
+    }
+
+    /// Serialize everything persisted so far to JSON, for example to move a wallet between stores
+    /// on different platforms. See `ChangeSet::to_json`; secret keys are not included.
+    ///
+    /// The store is only read: a SQLite store that holds no wallet yet exports an empty changeset
+    /// and is left without tables.
+    pub fn export_changeset_json(&self) -> Result<String, PersistenceError> {
+        let mut lock = self.inner.lock().unwrap();
+        let changeset = match lock.deref_mut() {
+            PersistenceType::Sql(conn) => {
+                let mut conn = conn.lock().unwrap();
+                if stored_schema_versions(&conn)?.contains_key(WALLET_SCHEMA_NAME) {
+                    let db_tx = conn.transaction()?;
+                    bdk_wallet::ChangeSet::from_sqlite(&db_tx)?
+                } else {
+                    bdk_wallet::ChangeSet::default()
+                }
+            }
+            persister => PersistenceType::initialize(persister)?,
+        };
+        ChangeSet::from(changeset)
+            .to_json()
+            .map_err(|e| PersistenceError::Reason {
+                error_message: e.to_string(),
+            })
+    }
+
+    /// Merge a JSON changeset produced by `export_changeset_json` into this store.
+    pub fn import_changeset_json(&self, json: String) -> Result<(), PersistenceError> {
+        let changeset: bdk_wallet::ChangeSet = ChangeSet::from_json(json)
+            .map_err(|e| PersistenceError::Reason {
+                error_message: e.to_string(),
+            })?
+            .into();
+        let mut lock = self.inner.lock().unwrap();
+        // make sure the tables exist before writing to a fresh store
+        PersistenceType::initialize(lock.deref_mut())?;
+        PersistenceType::persist(lock.deref_mut(), &changeset)
+    }
+
     /// Retrieve keychain metadata from a pre-v1 BDK SQLite wallet database.
     pub fn get_pre_v1_wallet_keychains(
         &self,
@@ -89,6 +171,42 @@ impl Persister {
     }
 }
 
+/// The table in which bdk records the version of each of its schemas.
+const SCHEMAS_TABLE_NAME: &str = "bdk_schemas";
+
+/// The schema of the wallet's own tables, next to those of its chain data.
+const WALLET_SCHEMA_NAME: &str = "bdk_wallet";
+
+/// Read the version of each schema present in the store.
+fn stored_schema_versions(conn: &BdkConnection) -> Result<HashMap<String, u32>, PersistenceError> {
+    let has_schemas: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+        [SCHEMAS_TABLE_NAME],
+        |row| row.get(0),
+    )?;
+    if !has_schemas {
+        return Ok(HashMap::new());
+    }
+    let mut statement = conn.prepare(&format!("SELECT name, version FROM {SCHEMAS_TABLE_NAME}"))?;
+    let versions = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<HashMap<String, u32>, _>>()?;
+    Ok(versions)
+}
+
+/// The schema versions this version of the library migrates a store to, found by initializing
+/// an empty store the first time they are needed.
+fn supported_schema_versions() -> Result<&'static HashMap<String, u32>, PersistenceError> {
+    static SUPPORTED: OnceLock<HashMap<String, u32>> = OnceLock::new();
+    if let Some(supported) = SUPPORTED.get() {
+        return Ok(supported);
+    }
+    let mut conn = BdkConnection::open_in_memory()?;
+    BdkConnection::initialize(&mut conn)?;
+    let supported = stored_schema_versions(&conn)?;
+    Ok(SUPPORTED.get_or_init(|| supported))
+}
+
 impl From<BdkPreV1WalletKeychain> for PreV1WalletKeychain {
     fn from(value: BdkPreV1WalletKeychain) -> Self {
         Self {
@@ -1,5 +1,23 @@
-use crate::bitcoin::{Address, AddressData, Key, Network, ProprietaryKey, Psbt};
+use crate::bitcoin::{
+    build_bip21_uri, parse_bip21_uri, Address, AddressData, AddressType, Amount, Block, FeeRate,
+    Key, Network, ProprietaryKey, Psbt, Script, SilentPaymentAddress, Transaction,
+};
+use crate::error::{
+    AddressParseError, FromScriptError, PsbtError, PsbtParseError, SignerError,
+    SilentPaymentAddressError, TransactionError,
+};
+use crate::keys::DescriptorSecretKey;
+use crate::tests::TestWallet;
+use crate::tx_builder::TxBuilder;
+use crate::types::SignOptions;
+use crate::wallet::{ForeignSigner, Wallet};
 use bdk_electrum::bdk_core::bitcoin::hex::DisplayHex;
+use bdk_wallet::bitcoin::constants::genesis_block;
+
+use std::sync::Arc;
+
+const FIRST_COSIGNER_TPRV: &str = "tprv8ZgxMBicQKsPdWuqM1t1CDRvQtQuBPyfL6GbhQwtxDKgUAVPbxmj71pRA8raTqLrec5LyTs5TqCxdABcZr77bt2KyWA5bizJHnC4g4ysm4h";
+const SECOND_COSIGNER_TPRV: &str = "tprv8ZgxMBicQKsPf2qfrEygW6fdYseJDDrVnDv26PH5BHdvSuG6ecCbHqLVof9yZcMoM31z9ur3tTYbSnr1WBqbGX97CbXcmp5H6qeMpyvx35B";
 
 #[test]
 fn test_is_valid_for_network() {
@@ -357,6 +375,133 @@ fn test_to_address_data() {
     assert!(matches!(segwit_data, AddressData::Segwit { .. }));
 }
 
+#[test]
+fn test_address_from_script() {
+    // P2WPKH scriptPubKey from BIP-173
+    let script = Script::new(vec![
+        0x00, 0x14, 0x75, 0x1e, 0x76, 0xe8, 0x19, 0x91, 0x96, 0xd4, 0x54, 0x94, 0x1c, 0x45, 0xd1,
+        0xb3, 0xa3, 0x23, 0xf1, 0x43, 0x3b, 0xd6,
+    ]);
+    let address = Address::from_script(Arc::new(script), Network::Bitcoin).unwrap();
+    assert_eq!(
+        address.to_string(),
+        "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+    );
+    assert_eq!(address.script_pubkey().to_bytes()[..2], [0x00, 0x14]);
+
+    // a bare OP_RETURN has no address form
+    let op_return = Script::new(vec![0x6a]);
+    assert!(matches!(
+        Address::from_script(Arc::new(op_return), Network::Bitcoin),
+        Err(FromScriptError::UnrecognizedScript)
+    ));
+}
+
+// from the BIP-352 test vectors
+const SILENT_PAYMENT_ADDRESS: &str = "sp1qqgste7k9hx0qftg6qmwlkqtwuy6cycyavzmzj85c6qdfhjdpdjtdgqjuexzk6murw56suy3e0rd2cgqvycxttddwsvgxe2usfpxumr70xc9pkqwv";
+
+#[test]
+fn test_silent_payment_address() {
+    let address =
+        SilentPaymentAddress::new(SILENT_PAYMENT_ADDRESS.to_string(), Network::Bitcoin).unwrap();
+    assert_eq!(address.version(), 0);
+    assert_eq!(
+        address.scan_key().to_lower_hex_string(),
+        "0220bcfac5b99e04ad1a06ddfb016ee13582609d60b6291e98d01a9bc9a16c96d4"
+    );
+    assert_eq!(
+        address.spend_key().to_lower_hex_string(),
+        "025cc9856d6f8375350e123978daac200c260cb5b5ae83106cab90484dcd8fcf36"
+    );
+    assert_eq!(address.to_string(), SILENT_PAYMENT_ADDRESS);
+
+    // upper case addresses are accepted and normalized
+    let upper =
+        SilentPaymentAddress::new(SILENT_PAYMENT_ADDRESS.to_uppercase(), Network::Bitcoin).unwrap();
+    assert_eq!(upper, address);
+
+    assert!(matches!(
+        SilentPaymentAddress::new(SILENT_PAYMENT_ADDRESS.to_string(), Network::Signet),
+        Err(SilentPaymentAddressError::NetworkValidation)
+    ));
+}
+
+#[test]
+fn test_malformed_silent_payment_address() {
+    // a single changed character breaks the checksum
+    let mut corrupted = SILENT_PAYMENT_ADDRESS.to_string();
+    corrupted.replace_range(10..11, "q");
+    assert_ne!(corrupted, SILENT_PAYMENT_ADDRESS);
+    assert!(matches!(
+        SilentPaymentAddress::new(corrupted, Network::Bitcoin),
+        Err(SilentPaymentAddressError::Bech32 { .. })
+    ));
+
+    // a segwit address is not a silent payment address
+    assert!(matches!(
+        SilentPaymentAddress::new(
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_string(),
+            Network::Bitcoin
+        ),
+        Err(SilentPaymentAddressError::UnknownHrp { hrp }) if hrp == "bc"
+    ));
+}
+
+#[test]
+fn test_psbt_inputs_and_outputs_info() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let recipient = Address::new(
+        "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".to_string(),
+        Network::Signet,
+    )
+    .unwrap()
+    .script_pubkey();
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(20_000)))
+        .fee_rate(&FeeRate::from_sat_per_vb(2).unwrap())
+        .finish(&wallet)
+        .unwrap();
+
+    let inputs = psbt.inputs();
+    assert_eq!(inputs.len(), 1);
+    let funding = wallet.transactions()[0].transaction.compute_txid();
+    assert_eq!(inputs[0].previous_output.txid, funding);
+    assert_eq!(inputs[0].value.as_ref().unwrap().to_sat(), 50_000);
+    let received_on = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 0)
+        .address
+        .script_pubkey();
+    assert_eq!(
+        inputs[0].script_pubkey.as_ref().unwrap().to_bytes(),
+        received_on.to_bytes()
+    );
+    assert!(inputs[0].is_mine);
+    assert_eq!(inputs[0].bip32_derivation.len(), 1);
+
+    let outputs = psbt.outputs();
+    assert_eq!(outputs.len(), 2);
+    let payment = outputs
+        .iter()
+        .find(|output| output.script_pubkey.to_bytes() == recipient.to_bytes())
+        .unwrap();
+    assert_eq!(payment.amount.to_sat(), 20_000);
+    assert!(!payment.is_mine);
+    assert!(payment.bip32_derivation.is_empty());
+
+    // the change output is flagged and its derivation path is on the internal keychain
+    let change = outputs.iter().find(|output| output.is_mine).unwrap();
+    let key_source = change.bip32_derivation.values().next().unwrap();
+    assert!(key_source.path.to_string().ends_with("/1/0"));
+    let derivation = wallet
+        .derivation_of_spk(change.script_pubkey.clone())
+        .unwrap();
+    assert_eq!(derivation.keychain, bdk_wallet::KeychainKind::Internal);
+    assert_eq!(
+        change.amount.to_sat() + payment.amount.to_sat() + psbt.fee().unwrap(),
+        50_000
+    );
+}
+
 #[test]
 fn test_psbt_spend_utxo() {
     let psbt = sample_psbt();
@@ -597,3 +742,423 @@ fn sample_psbt_taproot() -> Psbt {
     Psbt::new("cHNidP8BAH0CAAAAARblbcPN67JMY1pAsqbkYuqfh+OffiMD1PXBKuohxHUhAAAAAAD9////AkQRAAAAAAAAFgAU1Wm3y8dhQ9k1IXoe7Tu4/Vh4e2wVv/UFAAAAACJRILJL6QjSVc9B74yO2wV9qJ1D2HkxpgKV/LRX3dOOV+uMOAMAAAABASsA4fUFAAAAACJRIMSkYUKwqnaNBsaJxcZ1MKFYDd+ZEmqOaLTAGheYLSWeQRQZmDg8WRPva5p6l4cMrRyqdLSCYC74Gk1Mn1aimc9eDHAZu3+0gymYN/cLd5pvviwpc9YiW6HwxS7yCJ5umnS6QFa6MEJrll8dUVdGve8T2Q7nNfN27yTe0dWHAMEL4AvvpJddyZugvr1WuK5CfNdNvHUfuHsWalE8dXsM2XYvy4UiFcEZmDg8WRPva5p6l4cMrRyqdLSCYC74Gk1Mn1aimc9eDGkgGZg4PFkT72uaepeHDK0cqnS0gmAu+BpNTJ9WopnPXgysIGzdf1E91bpWIz3gwC+dFe5OS1a+SUQsP12wvvnaryY4uiCU7qCfqcnKJ7j6aL4hZr1iSn3Rrt04wcmnQwovyqPzWbpSnMAhFhmYODxZE+9rmnqXhwytHKp0tIJgLvgaTUyfVqKZz14MOQFwGbt/tIMpmDf3C3eab74sKXPWIluh8MUu8giebpp0ur6IapxWAACAAQAAgAAAAIAAAAAAAAAAACEWbN1/UT3VulYjPeDAL50V7k5LVr5JRCw/XbC++dqvJjg5AXAZu3+0gymYN/cLd5pvviwpc9YiW6HwxS7yCJ5umnS6WyNan1YAAIABAACAAAAAgAAAAAAAAAAAIRaU7qCfqcnKJ7j6aL4hZr1iSn3Rrt04wcmnQwovyqPzWTkBcBm7f7SDKZg39wt3mm++LClz1iJbofDFLvIInm6adLqsWpreVgAAgAEAAIAAAACAAAAAAAAAAAABFyAZmDg8WRPva5p6l4cMrRyqdLSCYC74Gk1Mn1aimc9eDAEYIHAZu3+0gymYN/cLd5pvviwpc9YiW6HwxS7yCJ5umnS6AAABBSAPfhtK8HCFezfCA8h1mRW3y5fvmffT2cUetRZ5HNtxRQEGawDAaCAPfhtK8HCFezfCA8h1mRW3y5fvmffT2cUetRZ5HNtxRawgoldONDrkvO54wrBh5Qjl6Be/p9isWgfyCsmznpkz3yC6IEANRlfXX/azlrWcSW9h4l1NL+SJx5Knd2gvMmVTh8vKulKcIQcPfhtK8HCFezfCA8h1mRW3y5fvmffT2cUetRZ5HNtxRTkBpcxekxLSoIeHxll9cboAcz0LEzV6rJUs5LlRnHL/wsW+iGqcVgAAgAEAAIAAAACAAQAAAAAAAAAhB0ANRlfXX/azlrWcSW9h4l1NL+SJx5Knd2gvMmVTh8vKOQGlzF6TEtKgh4fGWX1xugBzPQsTNXqslSzkuVGccv/Cxaxamt5WAACAAQAAgAAAAIABAAAAAAAAACEHoldONDrkvO54wrBh5Qjl6Be/p9isWgfyCsmznpkz3yA5AaXMXpMS0qCHh8ZZfXG6AHM9CxM1eqyVLOS5UZxy/8LFWyNan1YAAIABAACAAAAAgAEAAAAAAAAAAA==".to_string())
         .unwrap()
 }
+
+#[test]
+fn test_verify_merkle_proof() {
+    use crate::bitcoin::{TxMerkleNode, Txid};
+    use crate::types::MerkleProof;
+    use bdk_wallet::bitcoin::hashes::{sha256d, Hash};
+    use bdk_wallet::bitcoin::merkle_tree::calculate_root;
+    use bdk_wallet::bitcoin::Txid as BdkTxid;
+    use std::sync::Arc;
+
+    let txids: Vec<BdkTxid> = (1u8..=3)
+        .map(|byte| BdkTxid::from_raw_hash(sha256d::Hash::hash(&[byte])))
+        .collect();
+    let root = TxMerkleNode(calculate_root(txids.iter().copied()).unwrap().to_raw_hash());
+    let txid = |txid: BdkTxid| Arc::new(Txid(txid));
+
+    // the third transaction is paired with itself, then with the hash of the first two
+    let mut pair = txids[0].to_byte_array().to_vec();
+    pair.extend_from_slice(&txids[1].to_byte_array());
+    let first_pair = BdkTxid::from_raw_hash(sha256d::Hash::hash(&pair));
+    let proof = || MerkleProof {
+        block_height: 100,
+        merkle: vec![txid(txids[2]), txid(first_pair)],
+        pos: 2,
+    };
+
+    assert!(root.verify_merkle_proof(txid(txids[2]), proof()));
+    assert!(!root.verify_merkle_proof(txid(txids[0]), proof()));
+    assert!(!root.verify_merkle_proof(txid(txids[2]), MerkleProof { pos: 0, ..proof() }));
+}
+
+fn cosigner_tpub(tprv: &str) -> String {
+    DescriptorSecretKey::from_string(tprv.to_string())
+        .unwrap()
+        .as_public()
+        .to_string()
+}
+
+/// A 2-of-2 multisig wallet holding the secret key of one cosigner, funded with 50,000 sats.
+fn cosigner_wallet(first_key: &str, second_key: &str) -> Arc<Wallet> {
+    TestWallet::funded(&[50_000])
+        .descriptors(
+            &format!("wsh(multi(2,{first_key}/0/*,{second_key}/0/*))"),
+            &format!("wsh(multi(2,{first_key}/1/*,{second_key}/1/*))"),
+        )
+        .build()
+}
+
+/// Build an unsigned PSBT from the first cosigner's wallet, along with both cosigner wallets.
+fn multisig_psbt() -> (Arc<Wallet>, Arc<Wallet>, Arc<Psbt>) {
+    let first = cosigner_wallet(FIRST_COSIGNER_TPRV, &cosigner_tpub(SECOND_COSIGNER_TPRV));
+    let second = cosigner_wallet(&cosigner_tpub(FIRST_COSIGNER_TPRV), SECOND_COSIGNER_TPRV);
+    let recipient = first
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(20_000)))
+        .fee_rate(&FeeRate::from_sat_per_vb(2).unwrap())
+        .finish(&first)
+        .unwrap();
+    (first, second, psbt)
+}
+
+#[test]
+fn test_cosigner_signers() {
+    let wallet = cosigner_wallet(FIRST_COSIGNER_TPRV, &cosigner_tpub(SECOND_COSIGNER_TPRV));
+    let signers = wallet.signers(bdk_wallet::KeychainKind::External);
+    assert_eq!(signers.len(), 2);
+    assert_eq!(signers.iter().filter(|signer| signer.has_secret).count(), 1);
+    assert!(signers[0].has_secret);
+    assert!(!signers[1].has_secret);
+}
+
+/// Stands in for a hardware wallet holding the key of the second cosigner.
+struct CosignerDevice(Arc<Wallet>);
+
+impl ForeignSigner for CosignerDevice {
+    fn sign_psbt(&self, psbt: Arc<Psbt>) -> Result<Arc<Psbt>, SignerError> {
+        self.0.sign(psbt.clone(), None)?;
+        Ok(psbt)
+    }
+}
+
+struct DisconnectedDevice;
+
+impl ForeignSigner for DisconnectedDevice {
+    fn sign_psbt(&self, _psbt: Arc<Psbt>) -> Result<Arc<Psbt>, SignerError> {
+        Err(SignerError::UserCanceled)
+    }
+}
+
+#[test]
+fn test_foreign_signer() {
+    let (first, second, psbt) = multisig_psbt();
+    assert!(!first.sign(psbt.clone(), None).unwrap());
+
+    first.add_signer(
+        bdk_wallet::KeychainKind::External,
+        200,
+        Arc::new(CosignerDevice(second)),
+    );
+    assert!(first.sign(psbt.clone(), None).unwrap());
+    assert!(psbt.extract_tx().is_ok());
+
+    // errors of the device are reported as external signer errors
+    let (first, _, psbt) = multisig_psbt();
+    first.add_signer(
+        bdk_wallet::KeychainKind::External,
+        200,
+        Arc::new(DisconnectedDevice),
+    );
+    let result = first.sign(psbt, None);
+    assert!(matches!(result, Err(SignerError::External { .. })));
+}
+
+#[test]
+fn test_psbt_combine() {
+    let (first, second, psbt) = multisig_psbt();
+    let other = Arc::new(Psbt::new(psbt.serialize()).unwrap());
+
+    assert!(!first.sign(psbt.clone(), None).unwrap());
+    assert!(!second.sign(other.clone(), None).unwrap());
+    assert_eq!(psbt.input()[0].partial_sigs.len(), 1);
+    assert_eq!(other.input()[0].partial_sigs.len(), 1);
+
+    let combined = psbt.combine(other).unwrap();
+    assert_eq!(combined.input()[0].partial_sigs.len(), 2);
+
+    assert!(matches!(
+        combined.combine(Arc::new(sample_psbt())),
+        Err(PsbtError::UnexpectedUnsignedTx)
+    ));
+}
+
+#[test]
+fn test_psbt_finalize() {
+    let wallet = TestWallet::funded(&[20_000, 30_000]).build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(40_000)))
+        .finish(&wallet)
+        .unwrap();
+    let sign_options = SignOptions {
+        trust_witness_utxo: false,
+        assume_height: None,
+        allow_all_sighashes: false,
+        try_finalize: false,
+        sign_with_tap_internal_key: true,
+        allow_grinding: true,
+    };
+    wallet.sign(psbt.clone(), Some(sign_options)).unwrap();
+    assert_eq!(psbt.finalized_inputs(), vec![false, false]);
+
+    let result = psbt.finalize();
+
+    assert!(result.could_finalize);
+    assert!(result.errors.is_none());
+    assert_eq!(result.psbt.finalized_inputs(), vec![true, true]);
+}
+
+#[test]
+fn test_psbt_finalize_partially_signed() {
+    let (first, _, psbt) = multisig_psbt();
+    first.sign(psbt.clone(), None).unwrap();
+
+    let result = psbt.finalize();
+
+    assert!(!result.could_finalize);
+    assert_eq!(result.errors.unwrap().len(), 1);
+    assert_eq!(result.psbt.finalized_inputs(), vec![false]);
+}
+
+#[test]
+fn test_psbt_fee() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .fee_absolute(Arc::new(Amount::from_sat(1_234)))
+        .finish(&wallet)
+        .unwrap();
+
+    assert_eq!(psbt.fee().unwrap(), 1_234);
+    let unsigned_tx = psbt.0.lock().unwrap().unsigned_tx.clone();
+    let sent: u64 = unsigned_tx
+        .output
+        .iter()
+        .map(|output| output.value.to_sat())
+        .sum();
+    assert_eq!(sent + 1_234, 50_000);
+
+    let without_utxos = Psbt::from_unsigned_tx(Arc::new(unsigned_tx.into())).unwrap();
+    assert!(matches!(without_utxos.fee(), Err(PsbtError::MissingUtxo)));
+}
+
+#[test]
+fn test_psbt_bytes_round_trip() {
+    let psbt = sample_psbt();
+    let bytes = psbt.to_bytes();
+
+    assert_eq!(&bytes[..5], b"psbt\xff");
+    let decoded = Psbt::from_bytes(bytes.clone()).unwrap();
+    assert_eq!(decoded.serialize(), psbt.serialize());
+    assert_eq!(decoded.to_bytes(), bytes);
+
+    assert!(matches!(
+        Psbt::from_bytes(bytes[1..].to_vec()),
+        Err(PsbtParseError::PsbtEncoding { .. })
+    ));
+}
+
+fn genesis_coinbase() -> Transaction {
+    genesis_block(Network::Bitcoin).txdata[0].clone().into()
+}
+
+#[test]
+fn test_transaction_size() {
+    // The genesis block is 285 bytes: an 80 byte header, one byte for the transaction count and
+    // the 204 byte coinbase transaction, which has no witness.
+    let coinbase = genesis_coinbase();
+    assert_eq!(coinbase.total_size(), 204);
+    assert_eq!(coinbase.weight(), 816);
+    assert_eq!(coinbase.vsize(), 204);
+
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .finish(&wallet)
+        .unwrap();
+    assert!(wallet.sign(psbt.clone(), None).unwrap());
+    let signed = psbt.extract_tx().unwrap();
+
+    assert_eq!(signed.total_size(), signed.serialize().len() as u64);
+    assert_eq!(signed.vsize(), signed.weight().div_ceil(4));
+    // witness bytes are discounted, so the segwit transaction weighs less than four times its size
+    assert!(signed.weight() < 4 * signed.total_size());
+}
+
+#[test]
+fn test_block_from_bytes() {
+    let genesis = genesis_block(Network::Bitcoin);
+    let bytes = bdk_wallet::bitcoin::consensus::serialize(&genesis);
+    assert_eq!(bytes.len(), 285);
+
+    let block = Block::from_bytes(bytes.clone()).unwrap();
+    assert_eq!(block.block_hash().0, genesis.block_hash());
+    assert_eq!(block.header().nonce, genesis.header.nonce);
+    assert_eq!(block.txdata().len(), 1);
+    assert_eq!(block.txdata()[0].as_ref(), &genesis_coinbase());
+    assert_eq!(block.serialize(), bytes);
+
+    // a truncated block and trailing bytes are both rejected
+    assert!(Block::from_bytes(bytes[..284].to_vec()).is_err());
+    let mut extended = bytes;
+    extended.push(0);
+    assert!(matches!(
+        Block::from_bytes(extended),
+        Err(TransactionError::ParseFailed)
+    ));
+}
+
+#[test]
+fn test_transaction_shape() {
+    let coinbase = genesis_coinbase();
+    assert!(coinbase.is_coinbase());
+    assert!(!coinbase.is_explicitly_rbf());
+    assert_eq!(coinbase.input_count(), 1);
+    assert_eq!(coinbase.output_count(), 1);
+
+    let wallet = TestWallet::funded(&[20_000, 30_000]).build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(40_000)))
+        .finish(&wallet)
+        .unwrap();
+    let unsigned: Transaction = psbt.0.lock().unwrap().unsigned_tx.clone().into();
+
+    // transactions built by the wallet signal replaceability by default
+    assert!(!unsigned.is_coinbase());
+    assert!(unsigned.is_explicitly_rbf());
+    assert_eq!(unsigned.input_count(), 2);
+    assert_eq!(unsigned.output_count(), unsigned.output().len() as u64);
+}
+
+#[test]
+fn test_script_asm() {
+    let op_return = Script::new(vec![0x6a, 0x04, 0xde, 0xad, 0xbe, 0xef]);
+    assert!(op_return.is_op_return());
+    assert_eq!(
+        op_return.to_asm_string(),
+        "OP_RETURN OP_PUSHBYTES_4 deadbeef"
+    );
+    assert_eq!(op_return.to_string(), op_return.to_asm_string());
+
+    let address = Address::new(
+        "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".to_string(),
+        Network::Testnet,
+    )
+    .unwrap();
+    let p2wpkh = address.script_pubkey();
+    assert!(!p2wpkh.is_op_return());
+    assert_eq!(
+        p2wpkh.to_asm_string(),
+        "OP_0 OP_PUSHBYTES_20 751e76e8199196d454941c45d1b3a323f1433bd6"
+    );
+}
+
+#[test]
+fn test_bip21_uri_round_trip() {
+    let address = Arc::new(
+        Address::new(
+            "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".to_string(),
+            Network::Testnet,
+        )
+        .unwrap(),
+    );
+    let uri = build_bip21_uri(
+        address.clone(),
+        Some(Arc::new(Amount::from_sat(150_000))),
+        Some("Coffee & cake".to_string()),
+        None,
+    );
+
+    assert!(uri.starts_with("bitcoin:tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx?amount="));
+    assert!(uri.ends_with("&label=Coffee%20%26%20cake"));
+
+    let parsed = parse_bip21_uri(uri).unwrap();
+    assert_eq!(parsed.address, address);
+    assert!(parsed.address.is_valid_for_network(Network::Testnet));
+    assert_eq!(parsed.amount.unwrap().to_sat(), 150_000);
+    assert_eq!(parsed.label, Some("Coffee & cake".to_string()));
+    assert_eq!(parsed.message, None);
+
+    assert_eq!(
+        build_bip21_uri(address, None, None, None),
+        "bitcoin:tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"
+    );
+}
+
+#[test]
+fn test_bip21_uri_rejects_malformed_input() {
+    let parse = |uri: &str| parse_bip21_uri(uri.to_string());
+
+    assert!(parse("BITCOIN:tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx?foo=bar").is_ok());
+    assert!(matches!(
+        parse("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"),
+        Err(AddressParseError::InvalidBip21Uri { .. })
+    ));
+    assert!(matches!(
+        parse("bitcoin:tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsy"),
+        Err(AddressParseError::Bech32)
+    ));
+    assert!(matches!(
+        parse("bitcoin:tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx?amount=one"),
+        Err(AddressParseError::InvalidBip21Uri { error_message }) if error_message == "invalid amount: one"
+    ));
+    assert!(matches!(
+        parse("bitcoin:tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx?label=%FF"),
+        Err(AddressParseError::InvalidBip21Uri { .. })
+    ));
+    assert!(matches!(
+        parse(
+            "bitcoin:tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx?req-somethingyoudontunderstand=50"
+        ),
+        Err(AddressParseError::InvalidBip21Uri { .. })
+    ));
+
+    // the network is left for the caller to check
+    let mainnet = parse("bitcoin:bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").unwrap();
+    assert!(!mainnet.address.is_valid_for_network(Network::Testnet));
+}
+
+#[test]
+fn test_address_type() {
+    let cases = [
+        ("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa", AddressType::P2pkh),
+        ("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy", AddressType::P2sh),
+        (
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            AddressType::P2wpkh,
+        ),
+        (
+            "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3",
+            AddressType::P2wsh,
+        ),
+        (
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+            AddressType::P2tr,
+        ),
+    ];
+
+    for (address, expected_type) in cases {
+        let address = Address::new(address.to_string(), Network::Bitcoin).unwrap();
+        assert_eq!(address.address_type(), Some(expected_type));
+        assert!(address.is_valid_for_network(Network::Bitcoin));
+        assert!(!address.is_valid_for_network(Network::Regtest));
+    }
+
+    let testnet_address = Address::new(
+        "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".to_string(),
+        Network::Testnet,
+    )
+    .unwrap();
+    assert!(testnet_address.is_valid_for_network(Network::Signet));
+    assert!(!testnet_address.is_valid_for_network(Network::Regtest));
+}
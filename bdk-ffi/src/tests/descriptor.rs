@@ -1,10 +1,13 @@
 use crate::bitcoin::{Network, NetworkKind};
-use crate::descriptor::Descriptor;
+use crate::descriptor::{inspect_descriptor, verify_descriptor_checksum, Descriptor};
 use crate::error::DescriptorError;
-use crate::keys::{DerivationPath, DescriptorSecretKey, Mnemonic};
-use crate::types::KeychainKind;
+use crate::keys::{DerivationPath, DescriptorPublicKey, DescriptorSecretKey, Mnemonic};
+use crate::types::{
+    Bip48ScriptType, DescriptorInfo, DescriptorScriptType, KeychainKind, MultisigScriptType,
+};
 
 use assert_matches::assert_matches;
+use std::sync::Arc;
 
 fn get_descriptor_secret_key() -> DescriptorSecretKey {
     let mnemonic = Mnemonic::from_string("chaos fabric time speed sponsor all flat solution wisdom trophy crack object robot pave observe combine where aware bench orient secret primary cable detect".to_string()).unwrap();
@@ -36,13 +39,13 @@ fn test_descriptor_templates() {
         .as_public();
     // Public 86: [d1d04177/86'/1'/0']tpubDCJzjbcGbdEfXMWaL6QmgVmuSfXkrue7m2YNoacWwyc7a2XjXaKojRqNEbo41CFL3PyYmKdhwg2fkGpLX4SQCbQjCGxAkWHJTw9WEeenrJb/*
     let template_private_44 =
-        Descriptor::new_bip44(&master, KeychainKind::External, NetworkKind::Test);
+        Descriptor::new_bip44(&master, KeychainKind::External, NetworkKind::Test).unwrap();
     let template_private_49 =
-        Descriptor::new_bip49(&master, KeychainKind::External, NetworkKind::Test);
+        Descriptor::new_bip49(&master, KeychainKind::External, NetworkKind::Test).unwrap();
     let template_private_84 =
-        Descriptor::new_bip84(&master, KeychainKind::External, NetworkKind::Test);
+        Descriptor::new_bip84(&master, KeychainKind::External, NetworkKind::Test).unwrap();
     let template_private_86 =
-        Descriptor::new_bip86(&master, KeychainKind::External, NetworkKind::Test);
+        Descriptor::new_bip86(&master, KeychainKind::External, NetworkKind::Test).unwrap();
     // the extended public keys are the same when creating them manually as they are with the templates
     let template_public_44 = Descriptor::new_bip44_public(
         &handmade_public_44,
@@ -132,7 +135,42 @@ fn test_new_bip84_public_invalid_fingerprint() {
     )
     .unwrap_err();
 
-    assert_matches!(error, DescriptorError::Bip32 { .. });
+    assert_matches!(error, DescriptorError::InvalidFingerprint { .. });
+
+    let error = Descriptor::new_bip84_public(
+        &public_84,
+        "d1d0417".to_string(),
+        KeychainKind::External,
+        NetworkKind::Test,
+    )
+    .unwrap_err();
+
+    assert_matches!(error, DescriptorError::InvalidFingerprint { .. });
+}
+
+#[test]
+fn test_descriptor_templates_single_key_error() {
+    let single_key = DescriptorSecretKey::from_string(
+        "cVt4o7BGAig1UXywgGSmARhxMdzP5qvQsxKkSsc1XEkw3tDTQFpy".to_string(),
+    )
+    .unwrap();
+
+    assert_matches!(
+        Descriptor::new_bip44(&single_key, KeychainKind::External, NetworkKind::Test).unwrap_err(),
+        DescriptorError::InvalidKeyType
+    );
+    assert_matches!(
+        Descriptor::new_bip49(&single_key, KeychainKind::External, NetworkKind::Test).unwrap_err(),
+        DescriptorError::InvalidKeyType
+    );
+    assert_matches!(
+        Descriptor::new_bip84(&single_key, KeychainKind::External, NetworkKind::Test).unwrap_err(),
+        DescriptorError::InvalidKeyType
+    );
+    assert_matches!(
+        Descriptor::new_bip86(&single_key, KeychainKind::External, NetworkKind::Test).unwrap_err(),
+        DescriptorError::InvalidKeyType
+    );
 }
 
 #[test]
@@ -155,7 +193,8 @@ fn test_descriptor_derive_address() {
         &get_descriptor_secret_key(),
         KeychainKind::External,
         NetworkKind::Test,
-    );
+    )
+    .unwrap();
 
     let derived = descriptor
         .derive_address(0, Network::Testnet)
@@ -184,3 +223,368 @@ fn test_descriptor_derive_address_multipath_error() {
 
     assert_matches!(error, DescriptorError::MultiPath);
 }
+
+#[test]
+fn test_new_multisig() {
+    let keys: Vec<Arc<DescriptorPublicKey>> = vec![
+        "[d1d04177/84'/1'/0']tpubDDNxbq17egjFk2edjv8oLnzxk52zny9aAYNv9CMqTzA4mQDiQq818sEkNe9Gzmd4QU8558zftqbfoVBDQorG3E4Wq26tB2JeE4KUoahLkx6/0/*",
+        "[9a6a2580/84'/1'/0']tpubDDnGNapGEY6AZAdQbfRJgMg9fvz8pUBrLwvyvUqEgcUfgzM6zc2eVK4vY9x9L5FJWdX8WumXuLEDV5zDZnTfbn87vLe9XceCFwTu9so9Kks/0/*",
+        "[5bc5d243/84/2']tpubDAFG7XHSgRo927vaVKhcJAjuYW6AXJPunmS8So9ipV1xUyAUzEoBoiS5xSgPNBmjPMSnSXKjsJnTHWieJzUVxz8TUdWm8BUqgy4wL9yz5hp/1/*",
+    ]
+    .into_iter()
+    .map(|key| Arc::new(DescriptorPublicKey::from_string(key.to_string()).unwrap()))
+    .collect();
+
+    let sorted = Descriptor::new_multisig(
+        2,
+        keys.clone(),
+        true,
+        MultisigScriptType::Wsh,
+        NetworkKind::Test,
+    )
+    .unwrap();
+    assert_eq!(sorted.to_string(), "wsh(sortedmulti(2,[d1d04177/84'/1'/0']tpubDDNxbq17egjFk2edjv8oLnzxk52zny9aAYNv9CMqTzA4mQDiQq818sEkNe9Gzmd4QU8558zftqbfoVBDQorG3E4Wq26tB2JeE4KUoahLkx6/0/*,[9a6a2580/84'/1'/0']tpubDDnGNapGEY6AZAdQbfRJgMg9fvz8pUBrLwvyvUqEgcUfgzM6zc2eVK4vY9x9L5FJWdX8WumXuLEDV5zDZnTfbn87vLe9XceCFwTu9so9Kks/0/*,[5bc5d243/84/2']tpubDAFG7XHSgRo927vaVKhcJAjuYW6AXJPunmS8So9ipV1xUyAUzEoBoiS5xSgPNBmjPMSnSXKjsJnTHWieJzUVxz8TUdWm8BUqgy4wL9yz5hp/1/*))#thwucnlk");
+    // the descriptor round-trips through its string representation
+    let parsed = Descriptor::new(sorted.to_string(), NetworkKind::Test).unwrap();
+    assert_eq!(parsed.to_string(), sorted.to_string());
+
+    let unsorted = Descriptor::new_multisig(
+        2,
+        keys.clone(),
+        false,
+        MultisigScriptType::ShWsh,
+        NetworkKind::Test,
+    )
+    .unwrap();
+    assert_eq!(unsorted.to_string(), "sh(wsh(multi(2,[d1d04177/84'/1'/0']tpubDDNxbq17egjFk2edjv8oLnzxk52zny9aAYNv9CMqTzA4mQDiQq818sEkNe9Gzmd4QU8558zftqbfoVBDQorG3E4Wq26tB2JeE4KUoahLkx6/0/*,[9a6a2580/84'/1'/0']tpubDDnGNapGEY6AZAdQbfRJgMg9fvz8pUBrLwvyvUqEgcUfgzM6zc2eVK4vY9x9L5FJWdX8WumXuLEDV5zDZnTfbn87vLe9XceCFwTu9so9Kks/0/*,[5bc5d243/84/2']tpubDAFG7XHSgRo927vaVKhcJAjuYW6AXJPunmS8So9ipV1xUyAUzEoBoiS5xSgPNBmjPMSnSXKjsJnTHWieJzUVxz8TUdWm8BUqgy4wL9yz5hp/1/*)))#hhw7ngdh");
+
+    let error = Descriptor::new_multisig(
+        4,
+        keys.clone(),
+        true,
+        MultisigScriptType::Wsh,
+        NetworkKind::Test,
+    )
+    .unwrap_err();
+    assert_matches!(
+        error,
+        DescriptorError::InvalidThreshold {
+            threshold: 4,
+            key_count: 3
+        }
+    );
+
+    let error = Descriptor::new_multisig(0, keys, true, MultisigScriptType::Wsh, NetworkKind::Test)
+        .unwrap_err();
+    assert_matches!(error, DescriptorError::InvalidThreshold { .. });
+}
+
+#[test]
+fn test_new_bip48() {
+    let master = get_descriptor_secret_key();
+    let cosigner_account = master
+        .derive(&DerivationPath::new("m/48h/1h/1h/2h".to_string()).unwrap())
+        .unwrap()
+        .as_public();
+    let cosigner = Arc::new(
+        DescriptorPublicKey::new_bip48_public(
+            &cosigner_account,
+            "d1d04177".to_string(),
+            KeychainKind::External,
+            1,
+            Bip48ScriptType::P2wsh,
+            NetworkKind::Test,
+        )
+        .unwrap(),
+    );
+
+    let descriptor = Descriptor::new_bip48(
+        2,
+        &master,
+        vec![cosigner.clone()],
+        KeychainKind::External,
+        0,
+        Bip48ScriptType::P2wsh,
+        NetworkKind::Test,
+    )
+    .unwrap();
+    let descriptor_string = descriptor.to_string();
+    assert!(descriptor_string.starts_with("wsh(sortedmulti(2,[d1d04177/48'/1'/0'/2']tpub"));
+    assert!(descriptor_string.contains(",[d1d04177/48'/1'/1'/2']tpub"));
+
+    // the public variant builds the same descriptor from the account keys
+    let account_key = DescriptorSecretKey::new_bip48(
+        &master,
+        KeychainKind::External,
+        0,
+        Bip48ScriptType::P2wsh,
+        NetworkKind::Test,
+    )
+    .unwrap()
+    .as_public();
+    let public_descriptor = Descriptor::new_bip48_public(
+        2,
+        vec![account_key, cosigner.clone()],
+        Bip48ScriptType::P2wsh,
+        NetworkKind::Test,
+    )
+    .unwrap();
+    assert_eq!(public_descriptor.to_string(), descriptor_string);
+
+    let error = Descriptor::new_bip48_public(
+        1,
+        vec![cosigner.clone()],
+        Bip48ScriptType::P2shP2wsh,
+        NetworkKind::Test,
+    )
+    .unwrap_err();
+    assert_matches!(error, DescriptorError::KeyOriginMismatch);
+
+    let bip84_key = Arc::new(DescriptorPublicKey::from_string("[d1d04177/84'/1'/0']tpubDDNxbq17egjFk2edjv8oLnzxk52zny9aAYNv9CMqTzA4mQDiQq818sEkNe9Gzmd4QU8558zftqbfoVBDQorG3E4Wq26tB2JeE4KUoahLkx6/0/*".to_string()).unwrap());
+    let error = Descriptor::new_bip48(
+        1,
+        &master,
+        vec![bip84_key],
+        KeychainKind::External,
+        0,
+        Bip48ScriptType::P2wsh,
+        NetworkKind::Test,
+    )
+    .unwrap_err();
+    assert_matches!(error, DescriptorError::KeyOriginMismatch);
+
+    let error =
+        Descriptor::new_bip48_public(2, vec![cosigner], Bip48ScriptType::P2wsh, NetworkKind::Test)
+            .unwrap_err();
+    assert_matches!(
+        error,
+        DescriptorError::InvalidThreshold {
+            threshold: 2,
+            key_count: 1
+        }
+    );
+}
+
+#[test]
+fn test_derived_descriptor() {
+    let descriptor = Descriptor::new_bip84(
+        &get_descriptor_secret_key(),
+        KeychainKind::External,
+        NetworkKind::Test,
+    )
+    .unwrap();
+
+    let derived_0 = descriptor.derived_descriptor(0).unwrap();
+    let derived_1 = descriptor.derived_descriptor(1).unwrap();
+    assert!(!derived_0.has_wildcard());
+    assert!(derived_0
+        .to_string()
+        .starts_with("wpkh([d1d04177/84'/1'/0']tpub"));
+    assert!(derived_0.to_string().contains("/0/0)#"));
+    // secret keys are not carried over to the derived descriptor
+    assert_eq!(derived_0.to_string_with_secret(), derived_0.to_string());
+
+    let script_0 = derived_0
+        .derive_address(0, Network::Testnet)
+        .unwrap()
+        .script_pubkey();
+    let script_1 = derived_1
+        .derive_address(0, Network::Testnet)
+        .unwrap()
+        .script_pubkey();
+    assert_ne!(script_0.to_bytes(), script_1.to_bytes());
+    assert_eq!(
+        script_0.to_bytes(),
+        descriptor
+            .derive_address(0, Network::Testnet)
+            .unwrap()
+            .script_pubkey()
+            .to_bytes()
+    );
+
+    assert_matches!(
+        descriptor.derived_descriptor(1 << 31).unwrap_err(),
+        DescriptorError::HardenedDerivationXpub
+    );
+    assert_matches!(
+        derived_0.derived_descriptor(0).unwrap_err(),
+        DescriptorError::NotRanged
+    );
+}
+
+#[test]
+fn test_dust_value() {
+    let master = get_descriptor_secret_key();
+
+    let wpkh = Descriptor::new_bip84(&master, KeychainKind::External, NetworkKind::Test).unwrap();
+    assert_eq!(wpkh.dust_value().to_sat(), 294);
+
+    let tr = Descriptor::new_bip86(&master, KeychainKind::External, NetworkKind::Test).unwrap();
+    assert_eq!(tr.dust_value().to_sat(), 330);
+
+    let sh_wpkh =
+        Descriptor::new_bip49(&master, KeychainKind::External, NetworkKind::Test).unwrap();
+    assert_eq!(sh_wpkh.dust_value().to_sat(), 540);
+
+    let pkh = Descriptor::new_bip44(&master, KeychainKind::External, NetworkKind::Test).unwrap();
+    assert_eq!(pkh.dust_value().to_sat(), 546);
+}
+
+#[test]
+fn test_descriptor_script_type() {
+    let master = get_descriptor_secret_key();
+
+    let bip44 = Descriptor::new_bip44(&master, KeychainKind::External, NetworkKind::Test).unwrap();
+    assert_eq!(bip44.script_type(), DescriptorScriptType::P2pkh);
+    let bip49 = Descriptor::new_bip49(&master, KeychainKind::External, NetworkKind::Test).unwrap();
+    assert_eq!(bip49.script_type(), DescriptorScriptType::P2shP2wpkh);
+    let bip84 = Descriptor::new_bip84(&master, KeychainKind::External, NetworkKind::Test).unwrap();
+    assert_eq!(bip84.script_type(), DescriptorScriptType::P2wpkh);
+    let bip86 = Descriptor::new_bip86(&master, KeychainKind::External, NetworkKind::Test).unwrap();
+    assert_eq!(bip86.script_type(), DescriptorScriptType::P2tr);
+
+    let wsh = Descriptor::new(
+        "wsh(sortedmulti(1,tpubD6NzVbkrYhZ4WywdEfYbbd62yuvqLjAZuPsNyvzCNV85JekAEMbKHWSHLF9h3j45SxewXDcLv328B1SEZrxg4iwGfmdt1pDFjZiTkGiFqGa/0/*))".to_string(),
+        NetworkKind::Test,
+    )
+    .unwrap();
+    assert_eq!(wsh.script_type(), DescriptorScriptType::P2wsh);
+}
+
+#[test]
+fn test_descriptor_checksum() {
+    let descriptor = "wpkh([d1d04177/84'/1'/0']tpubDDNxbq17egjFk2edjv8oLnzxk52zny9aAYNv9CMqTzA4mQDiQq818sEkNe9Gzmd4QU8558zftqbfoVBDQorG3E4Wq26tB2JeE4KUoahLkx6/0/*)";
+    let parsed = Descriptor::new(descriptor.to_string(), NetworkKind::Test).unwrap();
+    let checksum = parsed.checksum();
+    assert_eq!(checksum.len(), 8);
+    assert_eq!(parsed.to_string(), format!("{descriptor}#{checksum}"));
+
+    // appending the checksum is accepted
+    let with_checksum = Descriptor::new(format!("{descriptor}#{checksum}"), NetworkKind::Test);
+    assert!(with_checksum.is_ok());
+
+    // a transcription error in the descriptor or in the checksum is caught
+    let tampered_descriptor = descriptor.replace("/0/*", "/1/*");
+    assert_matches!(
+        Descriptor::new(
+            format!("{tampered_descriptor}#{checksum}"),
+            NetworkKind::Test
+        ),
+        Err(DescriptorError::InvalidDescriptorChecksum)
+    );
+    let mut tampered_checksum: Vec<char> = checksum.chars().collect();
+    tampered_checksum[0] = if tampered_checksum[0] == 'q' {
+        'p'
+    } else {
+        'q'
+    };
+    let tampered_checksum: String = tampered_checksum.into_iter().collect();
+    assert_matches!(
+        Descriptor::new(
+            format!("{descriptor}#{tampered_checksum}"),
+            NetworkKind::Test
+        ),
+        Err(DescriptorError::InvalidDescriptorChecksum)
+    );
+}
+
+#[test]
+fn test_verify_descriptor_checksum() {
+    let descriptor = "wpkh([d1d04177/84'/1'/0']tpubDDNxbq17egjFk2edjv8oLnzxk52zny9aAYNv9CMqTzA4mQDiQq818sEkNe9Gzmd4QU8558zftqbfoVBDQorG3E4Wq26tB2JeE4KUoahLkx6/0/*)";
+    let checksum = Descriptor::new(descriptor.to_string(), NetworkKind::Test)
+        .unwrap()
+        .checksum();
+    assert!(verify_descriptor_checksum(format!(
+        "{descriptor}#{checksum}"
+    )));
+
+    let tampered_descriptor = descriptor.replace("/0/*", "/1/*");
+    assert!(!verify_descriptor_checksum(format!(
+        "{tampered_descriptor}#{checksum}"
+    )));
+    let tampered_checksum = if checksum.starts_with('q') {
+        checksum.replacen('q', "p", 1)
+    } else {
+        format!("q{}", &checksum[1..])
+    };
+    assert!(!verify_descriptor_checksum(format!(
+        "{descriptor}#{tampered_checksum}"
+    )));
+
+    // a descriptor without a checksum has nothing to verify
+    assert!(!verify_descriptor_checksum(descriptor.to_string()));
+}
+
+#[test]
+fn test_new_tr() {
+    let internal_key = "tpubD6NzVbkrYhZ4WywdEfYbbd62yuvqLjAZuPsNyvzCNV85JekAEMbKHWSHLF9h3j45SxewXDcLv328B1SEZrxg4iwGfmdt1pDFjZiTkGiFqGa/0/*";
+    let leaf_key = "[d1d04177/86'/1'/0']tpubDCJzjbcGbdEfXMWaL6QmgVmuSfXkrue7m2YNoacWwyc7a2XjXaKojRqNEbo41CFL3PyYmKdhwg2fkGpLX4SQCbQjCGxAkWHJTw9WEeenrJb/0/*";
+
+    let descriptor = Descriptor::new_tr(
+        internal_key.to_string(),
+        format!("pk({leaf_key})"),
+        NetworkKind::Test,
+    )
+    .unwrap();
+    assert_eq!(descriptor.script_type(), DescriptorScriptType::P2tr);
+    assert!(descriptor
+        .to_string()
+        .starts_with(&format!("tr({internal_key},pk({leaf_key}))")));
+    assert!(descriptor.derive_address(0, Network::Testnet).is_ok());
+
+    let branches = Descriptor::new_tr(
+        internal_key.to_string(),
+        format!("{{pk({leaf_key}),and_v(v:pk({leaf_key}),older(144))}}"),
+        NetworkKind::Test,
+    );
+    assert!(branches.is_ok());
+
+    // multi is not valid in tapscript, multi_a is
+    let invalid_leaf = Descriptor::new_tr(
+        internal_key.to_string(),
+        format!("multi(1,{leaf_key})"),
+        NetworkKind::Test,
+    );
+    assert!(invalid_leaf.is_err());
+}
+
+#[test]
+fn test_descriptor_info() {
+    let info =
+        |descriptor: &str| inspect_descriptor(descriptor.to_string(), NetworkKind::Test).unwrap();
+
+    assert_eq!(
+        info("wpkh(tprv8ZgxMBicQKsPf2qfrEygW6fdYseJDDrVnDv26PH5BHdvSuG6ecCbHqLVof9yZcMoM31z9ur3tTYbSnr1WBqbGX97CbXcmp5H6qeMpyvx35B/84h/1h/1h/0/*)"),
+        DescriptorInfo {
+            is_ranged: true,
+            is_multipath: false,
+            script_type: DescriptorScriptType::P2wpkh,
+            has_secret: true,
+        }
+    );
+    assert_eq!(
+        info("pkh(tpubD6NzVbkrYhZ4WywdEfYbbd62yuvqLjAZuPsNyvzCNV85JekAEMbKHWSHLF9h3j45SxewXDcLv328B1SEZrxg4iwGfmdt1pDFjZiTkGiFqGa/0/5)"),
+        DescriptorInfo {
+            is_ranged: false,
+            is_multipath: false,
+            script_type: DescriptorScriptType::P2pkh,
+            has_secret: false,
+        }
+    );
+    assert_eq!(
+        info("tr([9a6a2580/86'/1'/0']tpubDDnGNapGEY6AZAdQbfRJgMg9fvz8pUBrLwvyvUqEgcUfgzM6zc2eVK4vY9x9L5FJWdX8WumXuLEDV5zDZnTfbn87vLe9XceCFwTu9so9Kks/<0;1>/*)"),
+        DescriptorInfo {
+            is_ranged: true,
+            is_multipath: true,
+            script_type: DescriptorScriptType::P2tr,
+            has_secret: false,
+        }
+    );
+
+    // a descriptor for another network is rejected before any wallet exists
+    let mainnet = inspect_descriptor(
+        "wpkh(xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8/0/*)".to_string(),
+        NetworkKind::Test,
+    );
+    assert!(mainnet.is_err());
+}
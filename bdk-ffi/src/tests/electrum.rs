@@ -0,0 +1,220 @@
+use crate::electrum::{
+    fee_histogram_from_json, fee_rate_from_btc_per_kvb, run_blocking, ElectrumClient,
+};
+use crate::error::ElectrumError;
+use crate::tests::TestWallet;
+use crate::types::{wrap_full_scan_request, FullScanProgress};
+
+use bdk_wallet::KeychainKind;
+
+use bdk_wallet::bitcoin::consensus::encode::serialize_hex;
+use bdk_wallet::bitcoin::constants::genesis_block;
+use bdk_wallet::bitcoin::Network;
+use bdk_wallet::serde_json::{self, json, Value};
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[test]
+fn test_unreachable_socks5_proxy() {
+    // nothing listens on port 1, so dialing the proxy fails before reaching the server
+    let result = ElectrumClient::new(
+        "tcp://electrum.blockstream.info:50001".to_string(),
+        Some("127.0.0.1:1".to_string()),
+        true,
+        Some(1),
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_fee_rate_from_btc_per_kvb() {
+    // 0.00001 BTC/kvB is 1 sat/vB
+    let fee_rate = fee_rate_from_btc_per_kvb(0.00001).unwrap();
+    assert_eq!(fee_rate.to_sat_per_vb_ceil(), 1);
+    assert_eq!(fee_rate.to_sat_per_kwu(), 250);
+
+    let fee_rate = fee_rate_from_btc_per_kvb(0.00012345).unwrap();
+    assert_eq!(fee_rate.to_sat_per_kwu(), 3_086);
+
+    assert!(fee_rate_from_btc_per_kvb(-1.0).is_none());
+    assert!(fee_rate_from_btc_per_kvb(f64::NAN).is_none());
+}
+
+#[derive(Default)]
+struct ProgressRecorder {
+    reports: Mutex<Vec<(KeychainKind, u32, u32)>>,
+}
+
+impl FullScanProgress for ProgressRecorder {
+    fn on_progress(&self, keychain: KeychainKind, spk_index: u32, total_estimated: u32) {
+        self.reports
+            .lock()
+            .unwrap()
+            .push((keychain, spk_index, total_estimated));
+    }
+}
+
+#[test]
+fn test_full_scan_progress() {
+    let wallet = TestWallet::funded(&[]).build();
+    let request = wallet.start_full_scan().build().unwrap();
+    let request = request.0.lock().unwrap().take().unwrap();
+
+    let recorder = Arc::new(ProgressRecorder::default());
+    let (mut request, _) = wrap_full_scan_request(request, 20, Some(recorder.clone()), None);
+    for keychain in request.keychains() {
+        assert_eq!(request.iter_spks(keychain).take(3).count(), 3);
+    }
+
+    assert_eq!(
+        *recorder.reports.lock().unwrap(),
+        vec![
+            (KeychainKind::External, 0, 20),
+            (KeychainKind::External, 1, 21),
+            (KeychainKind::External, 2, 22),
+            (KeychainKind::Internal, 0, 20),
+            (KeychainKind::Internal, 1, 21),
+            (KeychainKind::Internal, 2, 22),
+        ]
+    );
+}
+
+#[test]
+fn test_run_blocking() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let caller = std::thread::current().id();
+
+    let result = runtime.block_on(run_blocking(move || Ok(std::thread::current().id())));
+    assert_ne!(result.unwrap(), caller);
+
+    let result: Result<(), ElectrumError> =
+        runtime.block_on(run_blocking(|| Err(ElectrumError::RequestAlreadyConsumed)));
+    assert!(matches!(result, Err(ElectrumError::RequestAlreadyConsumed)));
+
+    let result: Result<(), ElectrumError> =
+        runtime.block_on(run_blocking(|| panic!("electrum call panicked")));
+    assert!(matches!(
+        result,
+        Err(ElectrumError::Panicked { error_message }) if error_message == "electrum call panicked"
+    ));
+}
+
+/// Serve a chain holding only the genesis block of `network`, with no script history and a fixed
+/// `mempool.get_fee_histogram`, and return the url of the server.
+fn mock_electrum(network: Network) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("tcp://{}", listener.local_addr().unwrap());
+    let header = serialize_hex(&genesis_block(network).header);
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        for line in BufReader::new(stream).lines() {
+            let request: Value = serde_json::from_str(&line.unwrap()).unwrap();
+            let result = match request["method"].as_str() {
+                Some("blockchain.block.header") => json!(header),
+                Some("blockchain.block.headers") => json!({"count": 1, "hex": header, "max": 2016}),
+                Some("blockchain.headers.subscribe") => json!({"height": 0, "hex": header}),
+                Some("blockchain.scripthash.get_history") => json!([]),
+                Some("mempool.get_fee_histogram") => json!([[12.5, 15000], [2, 40000], [1, 90000]]),
+                _ => Value::Null,
+            };
+            let response = json!({"jsonrpc": "2.0", "id": request["id"], "result": result});
+            writeln!(writer, "{}", response).unwrap();
+        }
+    });
+    url
+}
+
+#[test]
+fn test_block_header_and_hash() {
+    let client = ElectrumClient::new(mock_electrum(Network::Regtest), None, true, Some(5)).unwrap();
+
+    let header = client.block_header(0).unwrap();
+    assert_eq!(header.version, 1);
+    assert_eq!(
+        header.prev_blockhash.to_string(),
+        "0000000000000000000000000000000000000000000000000000000000000000"
+    );
+    assert_eq!(
+        header.merkle_root.to_string(),
+        "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"
+    );
+    assert_eq!(header.time, 1296688602);
+    assert_eq!(header.bits, 0x207fffff);
+    assert_eq!(header.nonce, 2);
+
+    assert_eq!(
+        client.block_hash(0).unwrap().to_string(),
+        "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206"
+    );
+}
+
+#[test]
+fn test_fee_histogram() {
+    let client = ElectrumClient::new(mock_electrum(Network::Regtest), None, true, Some(5)).unwrap();
+
+    let histogram: Vec<(u64, u64)> = client
+        .fee_histogram()
+        .unwrap()
+        .iter()
+        .map(|entry| (entry.fee_rate.to_sat_per_kwu(), entry.vsize))
+        .collect();
+    assert_eq!(
+        histogram,
+        vec![(3_125, 15_000), (500, 40_000), (250, 90_000)]
+    );
+}
+
+#[test]
+fn test_full_scan_and_sync_async() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let client =
+        Arc::new(ElectrumClient::new(mock_electrum(Network::Signet), None, true, Some(5)).unwrap());
+    let wallet = TestWallet::funded(&[]).build();
+
+    let request = wallet.start_full_scan().build().unwrap();
+    let update = runtime
+        .block_on(
+            client
+                .clone()
+                .full_scan_async(request, 3, 2, false, None, None),
+        )
+        .unwrap();
+    assert!(update.0.tx_update.txs.is_empty());
+    wallet.apply_update(update).unwrap();
+
+    wallet.reveal_next_address(KeychainKind::External);
+    let request = wallet.start_sync_with_revealed_spks().build().unwrap();
+    let update = runtime
+        .block_on(client.sync_async(request, 2, false, None))
+        .unwrap();
+    assert!(update.0.tx_update.txs.is_empty());
+    wallet.apply_update(update).unwrap();
+    assert_eq!(wallet.latest_checkpoint().height, 0);
+}
+
+#[test]
+fn test_fee_histogram_from_json() {
+    assert!(fee_histogram_from_json(&json!([])).unwrap().is_empty());
+
+    for invalid in [
+        json!({}),
+        json!([[1]]),
+        json!([[1, 2, 3]]),
+        json!([["1", 100]]),
+        json!([[-1, 100]]),
+        json!([[1, -100]]),
+    ] {
+        assert!(matches!(
+            fee_histogram_from_json(&invalid),
+            Err(ElectrumError::InvalidResponse { .. })
+        ));
+    }
+}
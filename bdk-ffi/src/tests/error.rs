@@ -1,9 +1,13 @@
+use crate::bitcoin::Txid;
 use crate::error::{
-    Bip32Error, Bip39Error, CannotConnectError, DescriptorError, DescriptorKeyError, ElectrumError,
-    EsploraError, ExtractTxError, PsbtError, PsbtParseError, RequestBuilderError, SignerError,
+    Bip32Error, Bip39Error, CannotConnectError, CbfError, DescriptorError, DescriptorKeyError,
+    ElectrumError, EsploraError, ExtractTxError, LightClientBuilderError, PersistenceError,
+    PsbtError, PsbtParseError, RequestBuilderError, SignerError, SilentPaymentAddressError,
     TransactionError, TxidParseError,
 };
 
+use std::sync::Arc;
+
 #[test]
 fn test_error_bip32() {
     let cases = vec![
@@ -168,6 +172,28 @@ fn test_error_descriptor() {
             },
             "hex decoding error: Hexadecimal decoding error",
         ),
+        (
+            DescriptorError::InvalidKeyType,
+            "the descriptor template requires a single extended key",
+        ),
+        (
+            DescriptorError::InvalidFingerprint {
+                error_message: "invalid hex".to_string(),
+            },
+            "invalid fingerprint: invalid hex",
+        ),
+        (
+            DescriptorError::InvalidThreshold {
+                threshold: 4,
+                key_count: 3,
+            },
+            "invalid threshold 4 for 3 keys",
+        ),
+        (DescriptorError::NotRanged, "the descriptor is not ranged"),
+        (
+            DescriptorError::KeyOriginMismatch,
+            "the key origin or derivation path does not match the BIP-48 account path",
+        ),
     ];
 
     for (error, expected_message) in cases {
@@ -276,7 +302,22 @@ fn test_error_electrum_client() {
                 error_message: "message".to_string(),
             },
             "message",
-        )
+        ),
+        (
+            ElectrumError::FeeEstimateUnavailable { number_of_blocks: 6 },
+            "the server has no fee estimate for confirmation within 6 blocks",
+        ),
+        (ElectrumError::Cancelled, "the scan was cancelled"),
+        (
+            ElectrumError::Panicked {
+                error_message: "message".to_string(),
+            },
+            "the electrum call panicked: message",
+        ),
+        (
+            ElectrumError::ChainChanged { height: 840_000 },
+            "the chain changed at height 840000 while the sync was in progress",
+        ),
     ];
 
     for (error, expected_message) in cases {
@@ -331,6 +372,18 @@ fn test_error_esplora() {
             "invalid hex data returned: Invalid hex",
         ),
         (EsploraError::TransactionNotFound, "transaction not found"),
+        (
+            EsploraError::TransactionsNotFound {
+                txids: vec![Arc::new(
+                    Txid::from_string(
+                        "5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456"
+                            .to_string(),
+                    )
+                    .unwrap(),
+                )],
+            },
+            "transactions not found: [Txid(5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456)]",
+        ),
         (
             EsploraError::HeaderHeightNotFound { height: 123456 },
             "header height 123456 not found",
@@ -340,6 +393,17 @@ fn test_error_esplora() {
             EsploraError::RequestAlreadyConsumed,
             "the request has already been consumed",
         ),
+        (
+            EsploraError::ZeroScanParameter {
+                parameter: "stop_gap".to_string(),
+            },
+            "stop_gap must be greater than zero",
+        ),
+        (EsploraError::Cancelled, "the scan was cancelled"),
+        (
+            EsploraError::ChainChanged { height: 840_000 },
+            "the chain changed at height 840000 while the sync was in progress",
+        ),
     ];
 
     for (error, expected_message) in cases {
@@ -373,6 +437,50 @@ fn test_error_extract_tx() {
     }
 }
 
+#[test]
+fn test_error_cbf() {
+    let cases = vec![
+        (CbfError::NodeStopped, "the node is no longer running"),
+        (
+            CbfError::InvalidPeer {
+                error_message: "invalid host name: node..invalid".to_string(),
+            },
+            "invalid peer: invalid host name: node..invalid",
+        ),
+    ];
+
+    for (error, expected_message) in cases {
+        assert_eq!(error.to_string(), expected_message);
+    }
+}
+
+#[test]
+fn test_error_light_client_builder() {
+    let hostname = "node.invalid".to_string();
+    let cases = vec![
+        (
+            LightClientBuilderError::InvalidHostname {
+                hostname: hostname.clone(),
+            },
+            "invalid host name: node.invalid",
+        ),
+        (
+            LightClientBuilderError::UnresolvedHostname {
+                hostname: hostname.clone(),
+            },
+            "could not resolve host name: node.invalid",
+        ),
+        (
+            LightClientBuilderError::HostnameWithProxy { hostname },
+            "host name node.invalid cannot be resolved through the socks5 proxy",
+        ),
+    ];
+
+    for (error, expected_message) in cases {
+        assert_eq!(error.to_string(), expected_message);
+    }
+}
+
 #[test]
 fn test_error_inspect() {
     let cases = vec![(
@@ -385,6 +493,28 @@ fn test_error_inspect() {
     }
 }
 
+#[test]
+fn test_error_persistence() {
+    let cases = vec![
+        (
+            PersistenceError::IncompatibleSchema {
+                schema: "bdk_wallet".to_string(),
+                version: 3,
+                supported: 2,
+            },
+            "the bdk_wallet schema is at version 3, newer than the supported version 2",
+        ),
+        (
+            PersistenceError::SqliteOnly,
+            "schema versions are only tracked by sqlite persisters",
+        ),
+    ];
+
+    for (error, expected_message) in cases {
+        assert_eq!(error.to_string(), expected_message);
+    }
+}
+
 #[test]
 fn test_error_psbt() {
     let cases = vec![
@@ -594,6 +724,46 @@ fn test_signer_errors() {
     }
 }
 
+#[test]
+fn test_error_silent_payment_address() {
+    let cases = vec![
+        (
+            SilentPaymentAddressError::Bech32 {
+                error_message: "invalid checksum".to_string(),
+            },
+            "invalid bech32m encoding: invalid checksum",
+        ),
+        (
+            SilentPaymentAddressError::UnknownHrp {
+                hrp: "bc".to_string(),
+            },
+            "unknown human-readable part bc, expected sp or tsp",
+        ),
+        (
+            SilentPaymentAddressError::NetworkValidation,
+            "address is not valid for the requested network",
+        ),
+        (
+            SilentPaymentAddressError::UnsupportedVersion { version: 31 },
+            "unsupported silent payment address version 31",
+        ),
+        (
+            SilentPaymentAddressError::InvalidLength { length: 65 },
+            "invalid payload length of 65 bytes",
+        ),
+        (
+            SilentPaymentAddressError::InvalidPublicKey {
+                error_message: "malformed public key".to_string(),
+            },
+            "invalid public key: malformed public key",
+        ),
+    ];
+
+    for (error, expected_message) in cases {
+        assert_eq!(error.to_string(), expected_message);
+    }
+}
+
 #[test]
 fn test_error_transaction() {
     let cases = vec![
@@ -0,0 +1,359 @@
+use crate::bitcoin::{Script, Txid};
+use crate::error::EsploraError;
+use crate::esplora::EsploraClient;
+use crate::tests::TestWallet;
+use crate::types::{FullScanScriptInspector, KeychainKind, ScanToken, SyncScriptInspector};
+
+use bdk_wallet::bitcoin::constants::genesis_block;
+use bdk_wallet::bitcoin::Network;
+use bdk_wallet::serde_json::json;
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+// nothing listens on port 1, so every request fails to connect
+const UNREACHABLE_ESPLORA_URL: &str = "http://127.0.0.1:1";
+
+fn unreachable_client() -> EsploraClient {
+    EsploraClient::new(UNREACHABLE_ESPLORA_URL.to_string(), None, None, None)
+}
+
+fn txid() -> Arc<Txid> {
+    Arc::new(
+        Txid::from_string(
+            "5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456".to_string(),
+        )
+        .unwrap(),
+    )
+}
+
+#[test]
+fn test_get_tx_unreachable_server_is_an_error() {
+    let client = unreachable_client();
+
+    // a connection failure must not be mistaken for an unknown transaction
+    assert!(client.get_tx(txid()).is_err());
+}
+
+#[test]
+fn test_chain_tip_unreachable_server_is_an_error() {
+    let client = unreachable_client();
+
+    assert!(client.get_height().is_err());
+    assert!(client.get_tip_hash().is_err());
+}
+
+#[test]
+fn test_full_scan_rejects_zero_parameters() {
+    let client = unreachable_client();
+    let wallet = TestWallet::funded(&[]).build();
+    let request = wallet.start_full_scan().build().unwrap();
+
+    let result = client.full_scan(request.clone(), 0, 4, None);
+    assert!(matches!(
+        result,
+        Err(EsploraError::ZeroScanParameter { parameter }) if parameter == "stop_gap"
+    ));
+    let result = client.full_scan(request.clone(), 20, 0, None);
+    assert!(matches!(
+        result,
+        Err(EsploraError::ZeroScanParameter { parameter }) if parameter == "parallel_requests"
+    ));
+
+    // the request was not consumed by the rejected calls
+    assert!(request.0.lock().unwrap().is_some());
+}
+
+/// Serve `responses` in order, one per connection, and return the url of the server along with a
+/// counter of the connections that were accepted.
+fn mock_esplora<R: AsRef<str> + Send + 'static>(responses: Vec<R>) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let connections = Arc::new(AtomicUsize::new(0));
+    let counter = connections.clone();
+    thread::spawn(move || {
+        for response in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            counter.fetch_add(1, Ordering::SeqCst);
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                let read = stream.read(&mut buffer).unwrap();
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..read]);
+            }
+            stream.write_all(response.as_ref().as_bytes()).unwrap();
+        }
+    });
+    (url, connections)
+}
+
+const SERVICE_UNAVAILABLE: &str =
+    "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+const NOT_FOUND: &str = "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+const TIP_HEIGHT: &str = "HTTP/1.1 200 OK\r\nContent-Length: 3\r\nConnection: close\r\n\r\n123";
+
+#[test]
+fn test_retries_transient_errors() {
+    let (url, connections) =
+        mock_esplora(vec![SERVICE_UNAVAILABLE, SERVICE_UNAVAILABLE, TIP_HEIGHT]);
+    let client = EsploraClient::new(url, None, Some(5), Some(2));
+
+    assert_eq!(client.get_height().unwrap(), 123);
+    assert_eq!(connections.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn test_does_not_retry_client_errors() {
+    let (url, connections) = mock_esplora(vec![NOT_FOUND, TIP_HEIGHT]);
+    let client = EsploraClient::new(url, None, Some(5), Some(2));
+
+    assert!(matches!(
+        client.get_height(),
+        Err(EsploraError::HttpResponse { status: 404, .. })
+    ));
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+}
+
+const EMPTY_LIST: &str = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n[]";
+
+/// Cancels the token once the scan reaches the script at `index`.
+struct CancelAt {
+    token: Arc<ScanToken>,
+    index: u32,
+}
+
+impl FullScanScriptInspector for CancelAt {
+    fn inspect(&self, _keychain: KeychainKind, index: u32, _script: Arc<Script>) {
+        if index == self.index {
+            self.token.cancel();
+        }
+    }
+}
+
+/// A `/blocks` response listing only the signet genesis block, the chain tip of a `TestWallet`.
+fn genesis_blocks() -> String {
+    let genesis = genesis_block(Network::Signet);
+    let body = json!([{
+        "id": genesis.block_hash(),
+        "height": 0,
+        "version": genesis.header.version,
+        "timestamp": genesis.header.time,
+        "tx_count": genesis.txdata.len(),
+        "size": genesis.total_size(),
+        "weight": genesis.weight().to_wu(),
+        "merkle_root": genesis.header.merkle_root,
+        "previousblockhash": null,
+        "mediantime": genesis.header.time,
+        "nonce": genesis.header.nonce,
+        "bits": genesis.header.bits,
+        "difficulty": 1.0,
+    }])
+    .to_string();
+    format!(
+        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )
+}
+
+#[test]
+fn test_full_scan_cancelled_mid_scan() {
+    // every script history is empty, so without cancelling the scan would request 20 scripts
+    // per keychain after fetching the chain tip
+    let responses = std::iter::once(genesis_blocks())
+        .chain(std::iter::repeat(EMPTY_LIST.to_string()).take(50))
+        .collect();
+    let (url, connections) = mock_esplora(responses);
+    let client = EsploraClient::new(url, None, Some(5), None);
+    let wallet = TestWallet::funded(&[]).build();
+    let token = Arc::new(ScanToken::new());
+    let request = wallet
+        .start_full_scan()
+        .inspect_spks_for_all_keychains(Arc::new(CancelAt {
+            token: token.clone(),
+            index: 2,
+        }))
+        .unwrap()
+        .build()
+        .unwrap();
+
+    let result = client.full_scan(request, 20, 1, Some(token.clone()));
+    assert!(matches!(result, Err(EsploraError::Cancelled)));
+    // the chain tip and the first three scripts of the external keychain, rather than the 40
+    // scripts of a complete scan
+    assert_eq!(connections.load(Ordering::SeqCst), 4);
+
+    // a cancelled token stops the next scan before it makes any request
+    let request = wallet.start_full_scan().build().unwrap();
+    let result = client.full_scan(request.clone(), 20, 1, Some(token));
+    assert!(matches!(result, Err(EsploraError::Cancelled)));
+    assert!(request.0.lock().unwrap().is_some());
+    assert_eq!(connections.load(Ordering::SeqCst), 4);
+}
+
+/// Cancels the token once the sync reaches its second script.
+struct CancelAtSecondScript {
+    token: Arc<ScanToken>,
+    seen: AtomicUsize,
+}
+
+impl SyncScriptInspector for CancelAtSecondScript {
+    fn inspect(&self, _script: Arc<Script>, _total: u64) {
+        if self.seen.fetch_add(1, Ordering::SeqCst) == 1 {
+            self.token.cancel();
+        }
+    }
+}
+
+#[test]
+fn test_sync_cancelled_between_round_trips() {
+    // each round trip fetches the chain tip and then the history of one script
+    let responses = std::iter::repeat([genesis_blocks(), EMPTY_LIST.to_string()])
+        .take(5)
+        .flatten()
+        .collect();
+    let (url, connections) = mock_esplora(responses);
+    let client = EsploraClient::new(url, None, Some(5), None);
+    let wallet = TestWallet::funded(&[]).build();
+    wallet.reveal_addresses_to(KeychainKind::External, 4);
+
+    // a token that is never cancelled lets the whole sync complete
+    let request = wallet.start_sync_with_revealed_spks().build().unwrap();
+    let token = Arc::new(ScanToken::new());
+    assert!(client.sync(request, 1, Some(token)).is_ok());
+    assert_eq!(connections.load(Ordering::SeqCst), 10);
+
+    let (url, connections) = mock_esplora(vec![
+        genesis_blocks(),
+        EMPTY_LIST.to_string(),
+        genesis_blocks(),
+        EMPTY_LIST.to_string(),
+    ]);
+    let client = EsploraClient::new(url, None, Some(5), None);
+    let token = Arc::new(ScanToken::new());
+    let request = wallet
+        .start_sync_with_revealed_spks()
+        .inspect_spks(Arc::new(CancelAtSecondScript {
+            token: token.clone(),
+            seen: AtomicUsize::new(0),
+        }))
+        .unwrap()
+        .build()
+        .unwrap();
+
+    // the round trip for the second script is answered, then the sync stops
+    let result = client.sync(request, 1, Some(token));
+    assert!(matches!(result, Err(EsploraError::Cancelled)));
+    assert_eq!(connections.load(Ordering::SeqCst), 4);
+}
+
+/// Serve the raw transactions in `txs` by their txid, answering 404 for any other path, and return
+/// the url of the server.
+fn mock_esplora_txs(txs: HashMap<String, Vec<u8>>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                let read = stream.read(&mut buffer).unwrap();
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..read]);
+            }
+            let request = String::from_utf8_lossy(&request);
+            let path = request.split_whitespace().nth(1).unwrap_or_default();
+            let body = path
+                .strip_prefix("/tx/")
+                .and_then(|path| path.strip_suffix("/raw"))
+                .and_then(|txid| txs.get(txid));
+            match body {
+                Some(body) => {
+                    let header = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    );
+                    stream.write_all(header.as_bytes()).unwrap();
+                    stream.write_all(body).unwrap();
+                }
+                None => stream.write_all(NOT_FOUND.as_bytes()).unwrap(),
+            }
+        }
+    });
+    url
+}
+
+#[test]
+fn test_get_txs() {
+    let wallet = TestWallet::funded(&[10_000, 20_000, 30_000]).build();
+    let transactions: Vec<_> = wallet
+        .transactions()
+        .into_iter()
+        .map(|tx| tx.transaction)
+        .collect();
+    let served = transactions
+        .iter()
+        .map(|tx| (tx.compute_txid().to_string(), tx.serialize()))
+        .collect();
+    let client = EsploraClient::new(mock_esplora_txs(served), None, Some(5), None);
+
+    // the transactions come back in the order of the txids, not the order requests finish
+    let mut txids: Vec<_> = transactions.iter().map(|tx| tx.compute_txid()).collect();
+    txids.reverse();
+    let fetched = client.get_txs(txids.clone(), 2).unwrap();
+    let fetched_txids: Vec<_> = fetched.iter().map(|tx| tx.compute_txid()).collect();
+    assert_eq!(fetched_txids, txids);
+
+    // unknown transactions are reported rather than dropped
+    let mut with_unknown = txids.clone();
+    with_unknown.insert(1, txid());
+    match client.get_txs(with_unknown, 2) {
+        Err(EsploraError::TransactionsNotFound { txids }) => {
+            assert_eq!(txids, vec![txid()]);
+        }
+        result => panic!(
+            "expected TransactionsNotFound, got {:?}",
+            result.map(|txs| txs.len())
+        ),
+    }
+
+    assert!(matches!(
+        client.get_txs(txids, 0),
+        Err(EsploraError::ZeroScanParameter { parameter }) if parameter == "parallel_requests"
+    ));
+}
+
+const UNCONFIRMED_STATUS: &str =
+    "HTTP/1.1 200 OK\r\nContent-Length: 19\r\nConnection: close\r\n\r\n{\"confirmed\":false}";
+const CONFIRMED_STATUS: &str = "HTTP/1.1 200 OK\r\nContent-Length: 139\r\nConnection: close\r\n\r\n{\"confirmed\":true,\"block_height\":0,\"block_hash\":\"0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206\",\"block_time\":1296688602}";
+
+#[test]
+fn test_get_tx_status() {
+    let (url, _) = mock_esplora(vec![UNCONFIRMED_STATUS, CONFIRMED_STATUS]);
+    let client = EsploraClient::new(url, None, Some(5), None);
+
+    let status = client.get_tx_status(txid()).unwrap();
+    assert!(!status.confirmed);
+    assert_eq!(status.block_height, None);
+    assert!(status.block_hash.is_none());
+    assert_eq!(status.block_time, None);
+
+    let status = client.get_tx_status(txid()).unwrap();
+    assert!(status.confirmed);
+    assert_eq!(status.block_height, Some(0));
+    assert_eq!(
+        status.block_hash.unwrap().to_string(),
+        "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206"
+    );
+    assert_eq!(status.block_time, Some(1296688602));
+}
@@ -1,7 +1,10 @@
 use crate::bitcoin::NetworkKind;
-use crate::error::DescriptorKeyError;
+use crate::error::{Bip39Error, DescriptorError, DescriptorKeyError};
 use crate::keys::{DerivationPath, DescriptorPublicKey, DescriptorSecretKey, Mnemonic};
-use crate::types::WildcardType;
+use crate::types::{Bip48ScriptType, KeychainKind, WildcardType};
+
+use bdk_wallet::bitcoin::hex::DisplayHex;
+
 use std::sync::Arc;
 
 fn get_inner() -> DescriptorSecretKey {
@@ -197,3 +200,149 @@ fn test_add_wildcard() {
         Err(DescriptorKeyError::CannotChangeWildcardType)
     ));
 }
+
+#[test]
+fn test_bip48_keys() {
+    let master_dsk = get_inner();
+
+    let bip48_dsk = DescriptorSecretKey::new_bip48(
+        &master_dsk,
+        KeychainKind::External,
+        0,
+        Bip48ScriptType::P2wsh,
+        NetworkKind::Test,
+    )
+    .unwrap();
+    assert!(bip48_dsk
+        .to_string()
+        .starts_with("[d1d04177/48'/1'/0'/2']tprv"));
+    assert!(bip48_dsk.to_string().ends_with("/0/*"));
+
+    // the secret template matches a manual derivation of the account key
+    let handmade_dpk = derive_dsk(&master_dsk, "m/48h/1h/0h/2h")
+        .unwrap()
+        .as_public();
+    let handmade_dpk = extend_dpk(&handmade_dpk, "m/0")
+        .unwrap()
+        .add_wildcard()
+        .unwrap();
+    assert_eq!(bip48_dsk.as_public().to_string(), handmade_dpk.to_string());
+
+    // the public template attaches the origin to the account-level public key
+    let account_dpk = derive_dsk(&master_dsk, "m/48h/1h/0h/1h")
+        .unwrap()
+        .as_public();
+    let bip48_dpk = DescriptorPublicKey::new_bip48_public(
+        &account_dpk,
+        "d1d04177".to_string(),
+        KeychainKind::Internal,
+        0,
+        Bip48ScriptType::P2shP2wsh,
+        NetworkKind::Test,
+    )
+    .unwrap();
+    let bip48_dsk_internal = DescriptorSecretKey::new_bip48(
+        &master_dsk,
+        KeychainKind::Internal,
+        0,
+        Bip48ScriptType::P2shP2wsh,
+        NetworkKind::Test,
+    )
+    .unwrap();
+    assert!(bip48_dpk
+        .to_string()
+        .starts_with("[d1d04177/48'/1'/0'/1']tpub"));
+    assert!(bip48_dpk.to_string().ends_with("/1/*"));
+    assert_eq!(
+        bip48_dpk.to_string(),
+        bip48_dsk_internal.as_public().to_string()
+    );
+
+    let error = DescriptorPublicKey::new_bip48_public(
+        &account_dpk,
+        "d1d0417".to_string(),
+        KeychainKind::External,
+        0,
+        Bip48ScriptType::P2wsh,
+        NetworkKind::Test,
+    )
+    .unwrap_err();
+    assert!(matches!(error, DescriptorError::InvalidFingerprint { .. }));
+
+    // an origin that disagrees with the account path is rejected rather than overwritten
+    let error = DescriptorPublicKey::new_bip48_public(
+        &account_dpk,
+        "d1d04177".to_string(),
+        KeychainKind::External,
+        0,
+        Bip48ScriptType::P2wsh,
+        NetworkKind::Test,
+    )
+    .unwrap_err();
+    assert!(matches!(error, DescriptorError::KeyOriginMismatch));
+
+    // only a master key can be derived to the account path
+    let account_dsk = derive_dsk(&master_dsk, "m/48h/1h/0h/2h").unwrap();
+    let error = DescriptorSecretKey::new_bip48(
+        &account_dsk,
+        KeychainKind::External,
+        0,
+        Bip48ScriptType::P2wsh,
+        NetworkKind::Test,
+    )
+    .unwrap_err();
+    assert!(matches!(error, DescriptorError::KeyOriginMismatch));
+}
+
+#[test]
+fn test_mnemonic_to_seed() {
+    let mnemonic = Mnemonic::from_string(
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"
+            .to_string(),
+    )
+    .unwrap();
+
+    let seed = mnemonic.to_seed("TREZOR".to_string());
+
+    assert_eq!(seed.len(), 64);
+    assert_eq!(
+        seed.to_lower_hex_string(),
+        "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+    );
+    assert_ne!(mnemonic.to_seed(String::new()), seed);
+}
+
+#[test]
+fn test_mnemonic_from_entropy() {
+    let mnemonic = Mnemonic::from_entropy(vec![0x7f; 16]).unwrap();
+    assert_eq!(
+        mnemonic.to_string(),
+        "legal winner thank year wave sausage worth useful legal winner thank yellow"
+    );
+
+    for length in [16, 20, 24, 28, 32] {
+        let mnemonic = Mnemonic::from_entropy(vec![0u8; length]).unwrap();
+        assert_eq!(mnemonic.to_string().split(' ').count(), length * 3 / 4);
+    }
+
+    assert!(matches!(
+        Mnemonic::from_entropy(vec![0u8; 15]),
+        Err(Bip39Error::BadEntropyBitCount { bit_count: 120 })
+    ));
+}
+
+#[test]
+fn test_descriptor_public_key_origin() {
+    let key = DescriptorPublicKey::from_string("[d1d04177/84h/1h/0h]tpubD6NzVbkrYhZ4WywdEfYbbd62yuvqLjAZuPsNyvzCNV85JekAEMbKHWSHLF9h3j45SxewXDcLv328B1SEZrxg4iwGfmdt1pDFjZiTkGiFqGa/0/*".to_string()).unwrap();
+
+    assert_eq!(key.master_fingerprint(), "d1d04177");
+    assert_eq!(
+        key.derivation_path().unwrap().to_u32_vec(),
+        vec![0x8000_0054, 0x8000_0001, 0x8000_0000, 0]
+    );
+    assert!(!key.is_multipath());
+
+    let multipath_key = DescriptorPublicKey::from_string("[d1d04177/84h/1h/0h]tpubD6NzVbkrYhZ4WywdEfYbbd62yuvqLjAZuPsNyvzCNV85JekAEMbKHWSHLF9h3j45SxewXDcLv328B1SEZrxg4iwGfmdt1pDFjZiTkGiFqGa/<0;1>/*".to_string()).unwrap();
+    assert!(multipath_key.is_multipath());
+    assert!(multipath_key.derivation_path().is_none());
+}
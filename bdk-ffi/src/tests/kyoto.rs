@@ -0,0 +1,78 @@
+use crate::error::LightClientBuilderError;
+use crate::kyoto::{Info, IpAddress, NodeState, Peer, Socks5Proxy, Warning};
+
+use std::sync::Arc;
+
+#[test]
+fn test_node_state_transitions() {
+    let mut state = NodeState::Connecting;
+
+    state.apply_info(&Info::SuccessfulHandshake);
+    assert_eq!(state, NodeState::Connecting);
+
+    state.apply_info(&Info::ConnectionsMet);
+    assert_eq!(
+        state,
+        NodeState::SyncingFilters {
+            filters_downloaded_percent: 0.0
+        }
+    );
+
+    state.apply_info(&Info::Progress {
+        chain_height: 100,
+        filters_downloaded_percent: 50.0,
+    });
+    assert_eq!(
+        state,
+        NodeState::SyncingFilters {
+            filters_downloaded_percent: 50.0
+        }
+    );
+
+    state = NodeState::Synced;
+    state.apply_info(&Info::Progress {
+        chain_height: 101,
+        filters_downloaded_percent: 100.0,
+    });
+    assert_eq!(state, NodeState::Synced);
+
+    state.apply_warning(&Warning::PeerTimedOut);
+    assert_eq!(state, NodeState::Synced);
+
+    state.apply_warning(&Warning::NeedConnections);
+    assert_eq!(state, NodeState::Connecting);
+}
+
+#[test]
+fn test_peer_from_hostname() {
+    // an address literal resolves without a DNS lookup
+    let peer = Peer::from_hostname("127.0.0.1".to_string(), Some(18444), false).unwrap();
+    assert_eq!(peer.hostname().as_deref(), Some("127.0.0.1"));
+    assert!(peer.to_trusted_peer(None).is_ok());
+
+    let ip_peer = Peer::new(Arc::new(IpAddress::from_ipv4(127, 0, 0, 1)), None, true);
+    assert_eq!(ip_peer.hostname(), None);
+
+    // the name is not looked up outside a configured proxy
+    let proxy = Socks5Proxy {
+        address: Arc::new(IpAddress::from_ipv4(127, 0, 0, 1)),
+        port: 9050,
+    };
+    assert!(matches!(
+        peer.to_trusted_peer(Some(&proxy)),
+        Err(LightClientBuilderError::HostnameWithProxy { .. })
+    ));
+    assert!(ip_peer.to_trusted_peer(Some(&proxy)).is_ok());
+
+    for hostname in [
+        "",
+        "not a host name",
+        "node..example.com",
+        "-node.example.com",
+    ] {
+        assert!(matches!(
+            Peer::from_hostname(hostname.to_string(), None, false),
+            Err(LightClientBuilderError::InvalidHostname { .. })
+        ));
+    }
+}
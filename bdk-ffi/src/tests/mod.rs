@@ -1,6 +1,132 @@
 mod bitcoin;
 mod descriptor;
+mod electrum;
 mod error;
+mod esplora;
 mod keys;
+mod kyoto;
+mod store;
 mod tx_builder;
 mod wallet;
+
+use crate::bitcoin::{Network, NetworkKind};
+use crate::descriptor::Descriptor;
+use crate::store::Persister;
+use crate::types::UnconfirmedTx;
+use crate::wallet::Wallet;
+
+use bdk_wallet::bitcoin::hashes::Hash;
+use bdk_wallet::bitcoin::{
+    absolute, block, transaction, Amount as BdkAmount, Block as BdkBlock, CompactTarget,
+    OutPoint as BdkOutPoint, Transaction as BdkTransaction, TxIn as BdkTxIn,
+    TxMerkleNode as BdkTxMerkleNode, TxOut as BdkTxOut, Txid as BdkTxid,
+};
+use bdk_wallet::KeychainKind;
+
+use std::sync::Arc;
+
+pub(crate) const FUNDED_EXTERNAL_DESCRIPTOR: &str = "wpkh(tprv8ZgxMBicQKsPf2qfrEygW6fdYseJDDrVnDv26PH5BHdvSuG6ecCbHqLVof9yZcMoM31z9ur3tTYbSnr1WBqbGX97CbXcmp5H6qeMpyvx35B/84h/1h/1h/0/*)";
+pub(crate) const FUNDED_INTERNAL_DESCRIPTOR: &str = "wpkh(tprv8ZgxMBicQKsPf2qfrEygW6fdYseJDDrVnDv26PH5BHdvSuG6ecCbHqLVof9yZcMoM31z9ur3tTYbSnr1WBqbGX97CbXcmp5H6qeMpyvx35B/84h/1h/1h/1/*)";
+
+/// Builds an in-memory signet wallet that received one unconfirmed output per amount, each to a
+/// freshly revealed external address. No network access is required.
+pub(crate) struct TestWallet {
+    external_descriptor: String,
+    internal_descriptor: String,
+    persister: Option<Arc<Persister>>,
+    amounts: Vec<u64>,
+}
+
+impl TestWallet {
+    /// A wallet on the `FUNDED_*_DESCRIPTOR`s, funded with `amounts`.
+    pub(crate) fn funded(amounts: &[u64]) -> Self {
+        TestWallet {
+            external_descriptor: FUNDED_EXTERNAL_DESCRIPTOR.to_string(),
+            internal_descriptor: FUNDED_INTERNAL_DESCRIPTOR.to_string(),
+            persister: None,
+            amounts: amounts.to_vec(),
+        }
+    }
+
+    /// Use custom external and internal descriptors.
+    pub(crate) fn descriptors(
+        mut self,
+        external_descriptor: &str,
+        internal_descriptor: &str,
+    ) -> Self {
+        self.external_descriptor = external_descriptor.to_string();
+        self.internal_descriptor = internal_descriptor.to_string();
+        self
+    }
+
+    /// Create the wallet in `persister`. Only the initial wallet is persisted; the funding
+    /// transactions stay staged.
+    pub(crate) fn persister(mut self, persister: Arc<Persister>) -> Self {
+        self.persister = Some(persister);
+        self
+    }
+
+    pub(crate) fn build(self) -> Arc<Wallet> {
+        let persister = self
+            .persister
+            .unwrap_or_else(|| Arc::new(Persister::new_in_memory().unwrap()));
+        let wallet = Wallet::new(
+            Arc::new(Descriptor::new(self.external_descriptor, NetworkKind::Test).unwrap()),
+            Arc::new(Descriptor::new(self.internal_descriptor, NetworkKind::Test).unwrap()),
+            Network::Signet,
+            persister,
+            25,
+        )
+        .unwrap();
+
+        let unconfirmed_txs = self
+            .amounts
+            .iter()
+            .enumerate()
+            .map(|(index, amount)| {
+                let address = wallet.reveal_next_address(KeychainKind::External).address;
+                let tx = BdkTransaction {
+                    version: transaction::Version::TWO,
+                    lock_time: absolute::LockTime::ZERO,
+                    input: vec![BdkTxIn {
+                        previous_output: BdkOutPoint {
+                            txid: BdkTxid::from_byte_array([1; 32]),
+                            vout: index as u32,
+                        },
+                        ..Default::default()
+                    }],
+                    output: vec![BdkTxOut {
+                        value: BdkAmount::from_sat(*amount),
+                        script_pubkey: address.script_pubkey().0.clone(),
+                    }],
+                };
+                UnconfirmedTx {
+                    tx: Arc::new(tx.into()),
+                    last_seen: index as u64 + 1,
+                }
+            })
+            .collect();
+        wallet.apply_unconfirmed_txs(unconfirmed_txs);
+
+        Arc::new(wallet)
+    }
+}
+
+/// Connect a block holding `txdata` on top of the wallet's latest checkpoint, confirming the
+/// transactions at the new tip height.
+pub(crate) fn mine_block(wallet: &Wallet, txdata: Vec<BdkTransaction>) {
+    let mut wallet = wallet.get_wallet();
+    let tip = wallet.latest_checkpoint();
+    let block = BdkBlock {
+        header: block::Header {
+            version: block::Version::TWO,
+            prev_blockhash: tip.hash(),
+            merkle_root: BdkTxMerkleNode::all_zeros(),
+            time: tip.height() + 1,
+            bits: CompactTarget::from_consensus(0x207fffff),
+            nonce: 0,
+        },
+        txdata,
+    };
+    wallet.apply_block(&block, tip.height() + 1).unwrap();
+}
@@ -0,0 +1,111 @@
+use crate::bitcoin::{Network, NetworkKind};
+use crate::descriptor::Descriptor;
+use crate::error::PersistenceError;
+use crate::store::Persister;
+use crate::tests::{TestWallet, FUNDED_EXTERNAL_DESCRIPTOR, FUNDED_INTERNAL_DESCRIPTOR};
+use crate::types::ChangeSet;
+use crate::wallet::Wallet;
+
+use bdk_wallet::rusqlite::Connection as BdkConnection;
+use bdk_wallet::{KeychainKind, WalletPersister};
+
+use std::sync::Arc;
+
+fn descriptors() -> (Arc<Descriptor>, Arc<Descriptor>) {
+    (
+        Arc::new(
+            Descriptor::new(FUNDED_EXTERNAL_DESCRIPTOR.to_string(), NetworkKind::Test).unwrap(),
+        ),
+        Arc::new(
+            Descriptor::new(FUNDED_INTERNAL_DESCRIPTOR.to_string(), NetworkKind::Test).unwrap(),
+        ),
+    )
+}
+
+fn sqlite_path(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("bdk-ffi-{name}-{}.sqlite", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn test_schema_version() {
+    let persister = Arc::new(Persister::new_in_memory().unwrap());
+    assert_eq!(persister.schema_version().unwrap(), None);
+
+    let (descriptor, change_descriptor) = descriptors();
+    let wallet = Wallet::new(
+        descriptor,
+        change_descriptor,
+        Network::Signet,
+        persister.clone(),
+        25,
+    )
+    .unwrap();
+    wallet.persist(persister.clone()).unwrap();
+    assert!(persister.schema_version().unwrap().is_some());
+}
+
+#[test]
+fn test_newer_schema_is_rejected() {
+    let path = sqlite_path("newer-schema");
+    {
+        let mut conn = BdkConnection::open(&path).unwrap();
+        BdkConnection::initialize(&mut conn).unwrap();
+    }
+    let supported = Persister::new_sqlite(path.clone())
+        .unwrap()
+        .schema_version()
+        .unwrap()
+        .unwrap();
+
+    // a store written by a later release with one more wallet migration
+    BdkConnection::open(&path)
+        .unwrap()
+        .execute(
+            "UPDATE bdk_schemas SET version = version + 1 WHERE name = 'bdk_wallet'",
+            [],
+        )
+        .unwrap();
+    let result = Persister::new_sqlite(path.clone());
+    assert!(matches!(
+        result,
+        Err(PersistenceError::IncompatibleSchema { schema, version, supported: s })
+            if schema == "bdk_wallet" && version == supported + 1 && s == supported
+    ));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_changeset_json_export_import() {
+    let source = Arc::new(Persister::new_in_memory().unwrap());
+    let wallet = TestWallet::funded(&[25_000])
+        .persister(source.clone())
+        .build();
+    wallet.reveal_addresses_to(KeychainKind::External, 5);
+    wallet.persist(source.clone()).unwrap();
+
+    let json = source.export_changeset_json().unwrap();
+    let destination = Arc::new(Persister::new_in_memory().unwrap());
+    destination.import_changeset_json(json).unwrap();
+
+    let (descriptor, change_descriptor) = descriptors();
+    let restored = Wallet::load(descriptor, change_descriptor, destination, 25).unwrap();
+    assert_eq!(restored.network(), Network::Signet);
+    assert_eq!(restored.derivation_index(KeychainKind::External), Some(5));
+    assert_eq!(restored.balance().total.to_sat(), 25_000);
+    assert_eq!(
+        restored.transactions()[0].transaction.compute_txid(),
+        wallet.transactions()[0].transaction.compute_txid()
+    );
+}
+
+#[test]
+fn test_export_empty_store() {
+    let persister = Persister::new_in_memory().unwrap();
+    let json = persister.export_changeset_json().unwrap();
+    assert_eq!(json, ChangeSet::new().to_json().unwrap());
+    // exporting must not create the wallet tables
+    assert_eq!(persister.schema_version().unwrap(), None);
+}
@@ -1,13 +1,20 @@
-use crate::bitcoin::{Amount, Input, Network, NetworkKind, OutPoint, Script, TxOut};
+use crate::bitcoin::{Amount, FeeRate, Input, Network, NetworkKind, OutPoint, Script, TxOut, Txid};
 use crate::descriptor::Descriptor;
-use crate::error::SighashParseError;
+use crate::error::{CreateTxError, SighashParseError};
 use crate::esplora::EsploraClient;
 use crate::store::Persister;
-use crate::tx_builder::TxBuilder;
-use crate::types::FullScanScriptInspector;
+use crate::tests::TestWallet;
+use crate::tx_builder::{BumpFeeTxBuilder, CoinSelectionAlgorithm, TxBuilder, TxOrdering};
+use crate::types::{FullScanScriptInspector, LockTime, ScriptAmount, UnconfirmedTx};
 use crate::wallet::Wallet;
 
 use bdk_wallet::bitcoin::hashes::hex::FromHex;
+use bdk_wallet::bitcoin::hashes::Hash;
+use bdk_wallet::bitcoin::{
+    absolute, transaction, Amount as BdkAmount, OutPoint as BdkOutPoint,
+    Transaction as BdkTransaction, TxIn as BdkTxIn, TxOut as BdkTxOut, Txid as BdkTxid,
+};
+use bdk_wallet::KeychainKind;
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -79,14 +86,14 @@ fn create_and_sync_wallet() -> Wallet {
         25,
     )
     .unwrap();
-    let client = EsploraClient::new("https://mutinynet.com/api/".to_string(), None);
+    let client = EsploraClient::new("https://mutinynet.com/api/".to_string(), None, None, None);
     let full_scan_builder = wallet.start_full_scan();
     let full_scan_request = full_scan_builder
         .inspect_spks_for_all_keychains(Arc::new(FullScanInspector))
         .unwrap()
         .build()
         .unwrap();
-    let update = client.full_scan(full_scan_request, 10, 10).unwrap();
+    let update = client.full_scan(full_scan_request, 10, 10, None).unwrap();
     wallet.apply_update(update).unwrap();
     println!("Wallet balance: {:?}", wallet.balance().total.to_sat());
     wallet
@@ -579,3 +586,918 @@ fn test_combined_only_witness_utxo_and_foreign_utxo_with_finish() {
         );
     }
 }
+
+#[test]
+fn test_add_data() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+    let data = b"bdk-ffi".to_vec();
+
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .add_data(data.clone())
+        .finish(&wallet)
+        .unwrap();
+
+    let tx = psbt.0.lock().unwrap().unsigned_tx.clone();
+    let null_data = tx
+        .output
+        .iter()
+        .find(|output| output.script_pubkey.is_op_return())
+        .expect("transaction has a null-data output");
+    assert_eq!(null_data.value.to_sat(), 0);
+    assert_eq!(&null_data.script_pubkey.as_bytes()[2..], data.as_slice());
+}
+
+#[test]
+fn test_add_data_too_large() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+
+    let result = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .add_data(vec![0; 81])
+        .finish(&wallet);
+
+    assert!(matches!(
+        result,
+        Err(CreateTxError::OpReturnDataTooLarge { length: 81 })
+    ));
+}
+
+#[test]
+fn test_nlocktime() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+
+    let height_psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .nlocktime(LockTime::Blocks { height: 800_000 })
+        .finish(&wallet)
+        .unwrap();
+    let lock_time = height_psbt.0.lock().unwrap().unsigned_tx.lock_time;
+    assert!(lock_time.is_block_height());
+    assert_eq!(lock_time.to_consensus_u32(), 800_000);
+
+    let time_psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .nlocktime(LockTime::Seconds {
+            consensus_time: 1_700_000_000,
+        })
+        .finish(&wallet)
+        .unwrap();
+    let lock_time = time_psbt.0.lock().unwrap().unsigned_tx.lock_time;
+    assert!(lock_time.is_block_time());
+    assert_eq!(lock_time.to_consensus_u32(), 1_700_000_000);
+
+    let result = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .nlocktime(LockTime::Blocks {
+            height: 500_000_001,
+        })
+        .finish(&wallet);
+    assert!(matches!(
+        result,
+        Err(CreateTxError::LockTimeConversionError)
+    ));
+}
+
+#[test]
+fn test_coin_selection() {
+    let wallet = TestWallet::funded(&[10_000, 20_000, 30_000]).build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+
+    let selected_values = |algorithm: CoinSelectionAlgorithm| -> Vec<u64> {
+        let psbt = TxBuilder::new()
+            .add_recipient(&recipient, Arc::new(Amount::from_sat(15_000)))
+            .coin_selection(algorithm)
+            .finish(&wallet)
+            .unwrap();
+        let psbt = psbt.0.lock().unwrap();
+        let mut values: Vec<u64> = psbt
+            .inputs
+            .iter()
+            .map(|input| input.witness_utxo.as_ref().unwrap().value.to_sat())
+            .collect();
+        values.sort();
+        values
+    };
+
+    // largest first only needs the biggest output to fund the payment
+    assert_eq!(
+        selected_values(CoinSelectionAlgorithm::LargestFirst),
+        vec![30_000]
+    );
+    for algorithm in [
+        CoinSelectionAlgorithm::BranchAndBound,
+        CoinSelectionAlgorithm::OldestFirst,
+        CoinSelectionAlgorithm::SingleRandomDraw,
+    ] {
+        let total: u64 = selected_values(algorithm).iter().sum();
+        assert!(total > 15_000);
+    }
+}
+
+fn foreign_psbt_input(value: u64) -> Input {
+    Input {
+        non_witness_utxo: None,
+        witness_utxo: Some(TxOut {
+            value: Arc::new(Amount::from_sat(value)),
+            script_pubkey: Arc::new(Script::new(
+                Vec::from_hex("0014d85c2b71d0060b09c9886aeb815e50991dda124d").unwrap(),
+            )),
+        }),
+        partial_sigs: HashMap::new(),
+        sighash_type: None,
+        redeem_script: None,
+        witness_script: None,
+        bip32_derivation: HashMap::new(),
+        final_script_sig: None,
+        final_script_witness: None,
+        ripemd160_preimages: HashMap::new(),
+        sha256_preimages: HashMap::new(),
+        hash160_preimages: HashMap::new(),
+        hash256_preimages: HashMap::new(),
+        tap_key_sig: None,
+        tap_script_sigs: HashMap::new(),
+        tap_scripts: HashMap::new(),
+        tap_key_origins: HashMap::new(),
+        tap_internal_key: None,
+        tap_merkle_root: None,
+        proprietary: HashMap::new(),
+        unknown: HashMap::new(),
+    }
+}
+
+#[test]
+fn test_add_foreign_utxo_fee() {
+    let wallet = TestWallet::funded(&[20_000]).build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+    let outpoint = OutPoint {
+        txid: Arc::new(
+            crate::bitcoin::Txid::from_string(
+                "5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456".to_string(),
+            )
+            .unwrap(),
+        ),
+        vout: 0,
+    };
+    let fee_rate = Arc::new(FeeRate::from_sat_per_vb(2).unwrap());
+
+    // the payment needs both the wallet's and the foreign output to be funded
+    let build = |satisfaction_weight: u64| {
+        TxBuilder::new()
+            .add_recipient(&recipient, Arc::new(Amount::from_sat(60_000)))
+            .fee_rate(&fee_rate)
+            .only_witness_utxo()
+            .add_foreign_utxo(
+                outpoint.clone(),
+                foreign_psbt_input(50_000),
+                satisfaction_weight,
+            )
+            .unwrap()
+            .finish(&wallet)
+            .unwrap()
+    };
+
+    let psbt = build(108);
+    {
+        let inner = psbt.0.lock().unwrap();
+        assert_eq!(inner.inputs.len(), 2);
+        let foreign_index = inner
+            .unsigned_tx
+            .input
+            .iter()
+            .position(|input| input.previous_output == outpoint.clone().into())
+            .expect("the foreign outpoint is spent");
+        let foreign_input = &inner.inputs[foreign_index];
+        assert_eq!(
+            foreign_input.witness_utxo.as_ref().unwrap().value.to_sat(),
+            50_000
+        );
+        assert!(foreign_input.partial_sigs.is_empty());
+        assert!(foreign_input.final_script_witness.is_none());
+    }
+
+    // once the foreign txout is known the wallet agrees with the fee stored in the psbt
+    wallet.insert_txout(
+        outpoint.clone(),
+        foreign_psbt_input(50_000).witness_utxo.unwrap(),
+    );
+    let tx: crate::bitcoin::Transaction = psbt.0.lock().unwrap().unsigned_tx.clone().into();
+    assert_eq!(
+        wallet.calculate_fee(&tx).unwrap().to_sat(),
+        psbt.fee().unwrap()
+    );
+
+    // an extra 800 wu of satisfaction weight costs 200 vbytes at 2 sat/vb
+    let heavier_psbt = build(908);
+    assert_eq!(heavier_psbt.fee().unwrap() - psbt.fee().unwrap(), 400);
+}
+
+#[test]
+fn test_fee_absolute() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .fee_absolute(Arc::new(Amount::from_sat(1_234)))
+        .finish(&wallet)
+        .unwrap();
+    let tx: crate::bitcoin::Transaction = psbt.0.lock().unwrap().unsigned_tx.clone().into();
+    assert_eq!(wallet.calculate_fee(&tx).unwrap().to_sat(), 1_234);
+
+    let result = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .fee_rate(&FeeRate::from_sat_per_vb(2).unwrap())
+        .fee_absolute(Arc::new(Amount::from_sat(1_234)))
+        .finish(&wallet);
+    assert!(matches!(result, Err(CreateTxError::FeePolicyConflict)));
+}
+
+#[test]
+fn test_policy_path_selects_branch() {
+    // Spendable either with the wallet key alone, or with a second key after a relative timelock.
+    let external_descriptor = "wsh(or_d(pk(tprv8ZgxMBicQKsPf2qfrEygW6fdYseJDDrVnDv26PH5BHdvSuG6ecCbHqLVof9yZcMoM31z9ur3tTYbSnr1WBqbGX97CbXcmp5H6qeMpyvx35B/84h/1h/0h/0/*),and_v(v:pk(0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798),older(6))))";
+    let internal_descriptor = "wpkh(tprv8ZgxMBicQKsPf2qfrEygW6fdYseJDDrVnDv26PH5BHdvSuG6ecCbHqLVof9yZcMoM31z9ur3tTYbSnr1WBqbGX97CbXcmp5H6qeMpyvx35B/84h/1h/0h/1/*)";
+    let wallet = TestWallet::funded(&[50_000])
+        .descriptors(external_descriptor, internal_descriptor)
+        .build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::Internal, 10)
+        .address
+        .script_pubkey();
+
+    let policy = wallet
+        .policies(bdk_wallet::KeychainKind::External)
+        .unwrap()
+        .unwrap();
+    assert!(policy.requires_path());
+
+    let result = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .finish(&wallet);
+    assert!(matches!(
+        result,
+        Err(CreateTxError::SpendingPolicyRequired { .. })
+    ));
+
+    let input_sequence = |branch: u64| {
+        let policy_path = vec![(policy.id(), vec![branch])].into_iter().collect();
+        let psbt = TxBuilder::new()
+            .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+            .policy_path(policy_path, bdk_wallet::KeychainKind::External)
+            .finish(&wallet)
+            .unwrap();
+        let sequence = psbt.0.lock().unwrap().unsigned_tx.input[0].sequence;
+        sequence
+    };
+
+    // the timelocked branch must signal its relative locktime in the input sequence
+    assert_eq!(input_sequence(1).0, 6);
+    assert_ne!(input_sequence(0).0, 6);
+}
+
+#[test]
+fn test_version() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+
+    for version in 1..=3 {
+        let psbt = TxBuilder::new()
+            .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+            .version(version)
+            .finish(&wallet)
+            .unwrap();
+        assert_eq!(psbt.0.lock().unwrap().unsigned_tx.version.0, version);
+    }
+
+    for version in [-1, 0, 4] {
+        let result = TxBuilder::new()
+            .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+            .version(version)
+            .finish(&wallet);
+        assert!(matches!(
+            result,
+            Err(CreateTxError::UnsupportedVersion { version: v }) if v == version
+        ));
+    }
+}
+
+#[test]
+fn test_allow_dust() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+
+    let result = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(100)))
+        .allow_dust(false)
+        .finish(&wallet);
+    assert!(matches!(
+        result,
+        Err(CreateTxError::OutputBelowDustLimit { .. })
+    ));
+
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(100)))
+        .allow_dust(true)
+        .finish(&wallet)
+        .unwrap();
+    let tx = psbt.0.lock().unwrap().unsigned_tx.clone();
+    assert!(tx
+        .output
+        .iter()
+        .any(|output| output.script_pubkey == recipient.0 && output.value.to_sat() == 100));
+}
+
+#[test]
+fn test_set_exact_sequence() {
+    let wallet = TestWallet::funded(&[20_000, 20_000]).build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+
+    // a relative timelock of 144 blocks, which also signals replaceability
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(30_000)))
+        .set_exact_sequence(144)
+        .finish(&wallet)
+        .unwrap();
+    let tx = psbt.0.lock().unwrap().unsigned_tx.clone();
+    assert_eq!(tx.input.len(), 2);
+    for input in &tx.input {
+        assert_eq!(input.sequence.0, 144);
+        assert!(input.sequence.is_relative_lock_time());
+        assert!(input.sequence.is_rbf());
+    }
+}
+
+fn outpoint_with_value(wallet: &Wallet, value: u64) -> OutPoint {
+    wallet
+        .list_unspent()
+        .into_iter()
+        .find(|utxo| utxo.txout.value.to_sat() == value)
+        .unwrap()
+        .outpoint
+}
+
+fn spent_outpoints(psbt: &crate::bitcoin::Psbt) -> Vec<bdk_wallet::bitcoin::OutPoint> {
+    psbt.0
+        .lock()
+        .unwrap()
+        .unsigned_tx
+        .input
+        .iter()
+        .map(|input| input.previous_output)
+        .collect()
+}
+
+#[test]
+fn test_coin_control() {
+    let wallet = TestWallet::funded(&[10_000, 20_000, 30_000]).build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+    let pinned = outpoint_with_value(&wallet, 20_000);
+
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(5_000)))
+        .add_utxos(vec![pinned.clone()])
+        .manually_selected_only()
+        .finish(&wallet)
+        .unwrap();
+    assert_eq!(spent_outpoints(&psbt), vec![pinned.clone().into()]);
+
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(35_000)))
+        .unspendable(vec![outpoint_with_value(&wallet, 10_000)])
+        .finish(&wallet)
+        .unwrap();
+    let mut spent = spent_outpoints(&psbt);
+    spent.sort();
+    let mut expected: Vec<bdk_wallet::bitcoin::OutPoint> = vec![
+        pinned.clone().into(),
+        outpoint_with_value(&wallet, 30_000).into(),
+    ];
+    expected.sort();
+    assert_eq!(spent, expected);
+
+    let result = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(25_000)))
+        .add_utxo(pinned)
+        .manually_selected_only()
+        .finish(&wallet);
+    assert!(matches!(
+        result,
+        Err(CreateTxError::InsufficientFunds { .. })
+    ));
+
+    // without manual selection a target the whole wallet cannot cover is a coin selection error
+    let result = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(100_000)))
+        .finish(&wallet);
+    assert!(matches!(result, Err(CreateTxError::CoinSelection { .. })));
+}
+
+#[test]
+fn test_drain_wallet() {
+    let wallet = TestWallet::funded(&[10_000, 20_000, 30_000]).build();
+    let destination = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+
+    let psbt = TxBuilder::new()
+        .drain_wallet()
+        .drain_to(&destination)
+        .fee_rate(&FeeRate::from_sat_per_vb(2).unwrap())
+        .finish(&wallet)
+        .unwrap();
+
+    let tx = psbt.0.lock().unwrap().unsigned_tx.clone();
+    assert_eq!(tx.input.len(), 3);
+    assert_eq!(tx.output.len(), 1);
+    assert_eq!(tx.output[0].script_pubkey, destination.0);
+    assert_eq!(tx.output[0].value.to_sat(), 60_000 - psbt.fee().unwrap());
+}
+
+#[test]
+fn test_bip69_ordering() {
+    let wallet = TestWallet::funded(&[10_000, 20_000, 30_000, 40_000]).build();
+    let recipient = |index: u32| {
+        wallet
+            .peek_address(bdk_wallet::KeychainKind::External, index)
+            .address
+            .script_pubkey()
+    };
+
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient(10), Arc::new(Amount::from_sat(30_000)))
+        .add_recipient(&recipient(11), Arc::new(Amount::from_sat(20_000)))
+        .add_recipient(&recipient(12), Arc::new(Amount::from_sat(20_000)))
+        .drain_wallet()
+        .ordering(TxOrdering::Bip69Lexicographic)
+        .finish(&wallet)
+        .unwrap();
+    let tx = psbt.0.lock().unwrap().unsigned_tx.clone();
+
+    let inputs: Vec<(String, u32)> = tx
+        .input
+        .iter()
+        .map(|input| {
+            (
+                input.previous_output.txid.to_string(),
+                input.previous_output.vout,
+            )
+        })
+        .collect();
+    let mut sorted_inputs = inputs.clone();
+    sorted_inputs.sort();
+    assert_eq!(inputs.len(), 4);
+    assert_eq!(inputs, sorted_inputs);
+
+    let outputs: Vec<(u64, Vec<u8>)> = tx
+        .output
+        .iter()
+        .map(|output| (output.value.to_sat(), output.script_pubkey.to_bytes()))
+        .collect();
+    let mut sorted_outputs = outputs.clone();
+    sorted_outputs.sort();
+    assert_eq!(outputs.len(), 4);
+    assert_eq!(outputs, sorted_outputs);
+}
+
+#[test]
+fn test_change_policy() {
+    let wallet = TestWallet::funded(&[50_000, 30_000]).build();
+    let destination = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+
+    // spend part of one output back to the wallet to create a change output
+    let psbt = TxBuilder::new()
+        .add_recipient(&destination, Arc::new(Amount::from_sat(10_000)))
+        .add_utxo(outpoint_with_value(&wallet, 50_000))
+        .manually_selected_only()
+        .finish(&wallet)
+        .unwrap();
+    assert!(wallet.sign(psbt.clone(), None).unwrap());
+    wallet.apply_unconfirmed_txs(vec![UnconfirmedTx {
+        tx: psbt.extract_tx().unwrap(),
+        last_seen: 10,
+    }]);
+
+    let keychains_spent = |builder: Arc<TxBuilder>| {
+        let psbt = builder
+            .drain_wallet()
+            .drain_to(&destination)
+            .finish(&wallet)
+            .unwrap();
+        let utxos = wallet.list_unspent();
+        let mut keychains: Vec<bdk_wallet::KeychainKind> = spent_outpoints(&psbt)
+            .into_iter()
+            .map(|outpoint| {
+                utxos
+                    .iter()
+                    .find(|utxo| {
+                        bdk_wallet::bitcoin::OutPoint::from(utxo.outpoint.clone()) == outpoint
+                    })
+                    .unwrap()
+                    .keychain
+            })
+            .collect();
+        keychains.sort();
+        keychains
+    };
+
+    assert_eq!(
+        keychains_spent(Arc::new(TxBuilder::new())),
+        vec![
+            bdk_wallet::KeychainKind::External,
+            bdk_wallet::KeychainKind::External,
+            bdk_wallet::KeychainKind::Internal
+        ]
+    );
+    assert_eq!(
+        keychains_spent(TxBuilder::new().do_not_spend_change()),
+        vec![
+            bdk_wallet::KeychainKind::External,
+            bdk_wallet::KeychainKind::External
+        ]
+    );
+    assert_eq!(
+        keychains_spent(TxBuilder::new().only_spend_change()),
+        vec![bdk_wallet::KeychainKind::Internal]
+    );
+}
+
+#[test]
+fn test_bump_fee_allow_shrinking() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .fee_rate(&FeeRate::from_sat_per_vb(2).unwrap())
+        .finish(&wallet)
+        .unwrap();
+    let original_fee = psbt.fee().unwrap();
+    let tx = psbt.0.lock().unwrap().unsigned_tx.clone();
+    let change = tx
+        .output
+        .iter()
+        .find(|txout| txout.script_pubkey != recipient.0)
+        .unwrap()
+        .clone();
+    let txid = Arc::new(crate::bitcoin::Txid(tx.compute_txid()));
+    wallet.apply_unconfirmed_txs(vec![UnconfirmedTx {
+        tx: Arc::new(tx.into()),
+        last_seen: 10,
+    }]);
+
+    let bumped = BumpFeeTxBuilder::new(
+        txid.clone(),
+        Arc::new(FeeRate::from_sat_per_vb(10).unwrap()),
+    )
+    .allow_shrinking(Arc::new(Script(change.script_pubkey.clone())))
+    .finish(&wallet)
+    .unwrap();
+    let fee_delta = bumped.fee().unwrap() - original_fee;
+    assert!(fee_delta > 0);
+    let bumped_tx = bumped.0.lock().unwrap().unsigned_tx.clone();
+    assert_eq!(bumped_tx.output.len(), 2);
+    let shrunk = bumped_tx
+        .output
+        .iter()
+        .find(|txout| txout.script_pubkey == change.script_pubkey)
+        .unwrap();
+    assert_eq!(shrunk.value.to_sat(), change.value.to_sat() - fee_delta);
+    let paid = bumped_tx
+        .output
+        .iter()
+        .find(|txout| txout.script_pubkey == recipient.0)
+        .unwrap();
+    assert_eq!(paid.value.to_sat(), 10_000);
+
+    let unknown_script = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 20)
+        .address
+        .script_pubkey();
+    let result = BumpFeeTxBuilder::new(txid, Arc::new(FeeRate::from_sat_per_vb(10).unwrap()))
+        .allow_shrinking(unknown_script)
+        .finish(&wallet);
+    assert!(matches!(result, Err(CreateTxError::ShrinkOutputNotFound)));
+}
+
+#[test]
+fn test_bump_fee_shrink_payee_output() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    // a payee outside the wallet
+    let payee = Arc::new(Script::new(
+        Vec::from_hex("0014000102030405060708090a0b0c0d0e0f10111213").unwrap(),
+    ));
+    let psbt = TxBuilder::new()
+        .add_recipient(&payee, Arc::new(Amount::from_sat(10_000)))
+        .fee_rate(&FeeRate::from_sat_per_vb(2).unwrap())
+        .finish(&wallet)
+        .unwrap();
+    let original_fee = psbt.fee().unwrap();
+    let tx = psbt.0.lock().unwrap().unsigned_tx.clone();
+    let change = tx
+        .output
+        .iter()
+        .find(|txout| txout.script_pubkey != payee.0)
+        .unwrap()
+        .clone();
+    let txid = Arc::new(crate::bitcoin::Txid(tx.compute_txid()));
+    wallet.apply_unconfirmed_txs(vec![UnconfirmedTx {
+        tx: Arc::new(tx.into()),
+        last_seen: 10,
+    }]);
+
+    let bumped = BumpFeeTxBuilder::new(txid, Arc::new(FeeRate::from_sat_per_vb(10).unwrap()))
+        .allow_shrinking(payee.clone())
+        .finish(&wallet)
+        .unwrap();
+    let fee_delta = bumped.fee().unwrap() - original_fee;
+    assert!(fee_delta > 0);
+    let bumped_tx = bumped.0.lock().unwrap().unsigned_tx.clone();
+    assert_eq!(bumped_tx.input.len(), 1);
+    assert_eq!(bumped_tx.output.len(), 2);
+
+    // the payee pays for the bump and the change of the wallet is left untouched
+    let paid = bumped_tx
+        .output
+        .iter()
+        .find(|txout| txout.script_pubkey == payee.0)
+        .unwrap();
+    assert_eq!(paid.value.to_sat(), 10_000 - fee_delta);
+    let kept = bumped_tx
+        .output
+        .iter()
+        .find(|txout| txout.script_pubkey == change.script_pubkey)
+        .unwrap();
+    assert_eq!(kept.value, change.value);
+}
+
+#[test]
+fn test_bump_fee_keeps_locktime() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .nlocktime(LockTime::Blocks { height: 100 })
+        .finish(&wallet)
+        .unwrap();
+    let tx = psbt.0.lock().unwrap().unsigned_tx.clone();
+    let txid = Arc::new(crate::bitcoin::Txid(tx.compute_txid()));
+    wallet.apply_unconfirmed_txs(vec![UnconfirmedTx {
+        tx: Arc::new(tx.into()),
+        last_seen: 10,
+    }]);
+    let fee_rate = Arc::new(FeeRate::from_sat_per_vb(10).unwrap());
+    let bumped_locktime = |builder: Arc<BumpFeeTxBuilder>| {
+        let psbt = builder.finish(&wallet).unwrap();
+        let tx = psbt.0.lock().unwrap().unsigned_tx.clone();
+        tx.lock_time
+    };
+
+    let builder = BumpFeeTxBuilder::new(txid.clone(), fee_rate.clone()).current_height(150);
+    assert_eq!(
+        bumped_locktime(builder),
+        bdk_wallet::bitcoin::absolute::LockTime::from_height(100).unwrap()
+    );
+
+    let builder = BumpFeeTxBuilder::new(txid, fee_rate).nlocktime(LockTime::Blocks { height: 200 });
+    assert_eq!(
+        bumped_locktime(builder),
+        bdk_wallet::bitcoin::absolute::LockTime::from_height(200).unwrap()
+    );
+}
+
+#[test]
+fn test_add_recipients() {
+    let wallet = TestWallet::funded(&[200_000]).build();
+    let first = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+    let payouts: Vec<ScriptAmount> = (11..21)
+        .map(|index| ScriptAmount {
+            script: wallet
+                .peek_address(bdk_wallet::KeychainKind::External, index)
+                .address
+                .script_pubkey(),
+            amount: Arc::new(Amount::from_sat(1_000 * index as u64)),
+        })
+        .collect();
+    let expected: Vec<(bdk_wallet::bitcoin::ScriptBuf, u64)> =
+        std::iter::once((first.0.clone(), 5_000))
+            .chain(
+                payouts
+                    .iter()
+                    .map(|payout| (payout.script.0.clone(), payout.amount.to_sat())),
+            )
+            .collect();
+
+    let psbt = TxBuilder::new()
+        .add_recipient(&first, Arc::new(Amount::from_sat(5_000)))
+        .add_recipients(payouts)
+        .ordering(TxOrdering::Untouched)
+        .finish(&wallet)
+        .unwrap();
+
+    let tx = psbt.0.lock().unwrap().unsigned_tx.clone();
+    let outputs: Vec<(bdk_wallet::bitcoin::ScriptBuf, u64)> = tx
+        .output
+        .iter()
+        .take(11)
+        .map(|txout| (txout.script_pubkey.clone(), txout.value.to_sat()))
+        .collect();
+    assert_eq!(outputs, expected);
+}
+
+#[test]
+fn test_bump_fee_minimum_fee_rate() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .fee_rate(&FeeRate::from_sat_per_vb(5).unwrap())
+        .finish(&wallet)
+        .unwrap();
+    let tx = psbt.0.lock().unwrap().unsigned_tx.clone();
+    let txid = Arc::new(crate::bitcoin::Txid(tx.compute_txid()));
+    let tx: crate::bitcoin::Transaction = tx.into();
+    let original_fee_rate = wallet.calculate_fee_rate(&tx).unwrap();
+    wallet.apply_unconfirmed_txs(vec![UnconfirmedTx {
+        tx: Arc::new(tx),
+        last_seen: 10,
+    }]);
+
+    let minimum = BumpFeeTxBuilder::new(txid.clone(), original_fee_rate.clone())
+        .minimum_bump_fee_rate(&wallet)
+        .unwrap();
+    assert_eq!(
+        minimum.to_sat_per_kwu(),
+        original_fee_rate.to_sat_per_kwu() + 250
+    );
+
+    let result = BumpFeeTxBuilder::new(txid.clone(), original_fee_rate).finish(&wallet);
+    assert!(matches!(result, Err(CreateTxError::FeeRateTooLow { .. })));
+
+    let bumped = BumpFeeTxBuilder::new(txid, minimum.clone())
+        .finish(&wallet)
+        .unwrap();
+    let bumped_tx: crate::bitcoin::Transaction =
+        bumped.0.lock().unwrap().unsigned_tx.clone().into();
+    assert!(
+        wallet
+            .calculate_fee_rate(&bumped_tx)
+            .unwrap()
+            .to_sat_per_kwu()
+            >= minimum.to_sat_per_kwu()
+    );
+}
+
+#[test]
+fn test_bump_fee_minimum_fee_rate_errors() {
+    let wallet = TestWallet::funded(&[]).build();
+    let fee_rate = Arc::new(FeeRate::from_sat_per_vb(5).unwrap());
+
+    // an unknown transaction is reported the same way by both methods
+    let unknown = Arc::new(Txid(BdkTxid::from_byte_array([3; 32])));
+    let result =
+        BumpFeeTxBuilder::new(unknown.clone(), fee_rate.clone()).minimum_bump_fee_rate(&wallet);
+    assert!(matches!(
+        result,
+        Err(CreateTxError::TransactionNotFound { .. })
+    ));
+    let result = BumpFeeTxBuilder::new(unknown, fee_rate.clone()).finish(&wallet);
+    assert!(matches!(
+        result,
+        Err(CreateTxError::TransactionNotFound { .. })
+    ));
+
+    // a payment to the wallet whose input the wallet has never seen has no known fee
+    let tx = BdkTransaction {
+        version: transaction::Version::TWO,
+        lock_time: absolute::LockTime::ZERO,
+        input: vec![BdkTxIn {
+            previous_output: BdkOutPoint {
+                txid: BdkTxid::from_byte_array([4; 32]),
+                vout: 0,
+            },
+            ..Default::default()
+        }],
+        output: vec![BdkTxOut {
+            value: BdkAmount::from_sat(10_000),
+            script_pubkey: wallet
+                .peek_address(KeychainKind::External, 0)
+                .address
+                .script_pubkey()
+                .0
+                .clone(),
+        }],
+    };
+    let txid = Arc::new(Txid(tx.compute_txid()));
+    wallet.apply_unconfirmed_txs(vec![UnconfirmedTx {
+        tx: Arc::new(tx.into()),
+        last_seen: 10,
+    }]);
+    let result = BumpFeeTxBuilder::new(txid, fee_rate).minimum_bump_fee_rate(&wallet);
+    assert!(matches!(result, Err(CreateTxError::CalculateFee { .. })));
+}
+
+#[test]
+fn test_preview_selection() {
+    let wallet = TestWallet::funded(&[60_000]).build();
+    let recipient = wallet
+        .peek_address(bdk_wallet::KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+    let fee_rate = Arc::new(FeeRate::from_sat_per_vb(5).unwrap());
+    let utxos_before = wallet.list_unspent().len();
+    let staged_before = wallet.staged().unwrap().to_json().unwrap();
+    let change_index_before = wallet.next_derivation_index(bdk_wallet::KeychainKind::Internal);
+
+    let preview = wallet
+        .preview_selection(
+            vec![ScriptAmount {
+                script: recipient.clone(),
+                amount: Arc::new(Amount::from_sat(25_000)),
+            }],
+            fee_rate.clone(),
+        )
+        .unwrap();
+    assert_eq!(preview.selected.len(), 1);
+    assert_eq!(preview.total_input.to_sat(), 60_000);
+    let change = preview.change.as_ref().unwrap().to_sat();
+    assert_eq!(change + preview.fee.to_sat(), 35_000);
+
+    // the preview leaves the change address free and the coins unspent
+    assert!(!wallet
+        .get_wallet()
+        .spk_index()
+        .is_used(bdk_wallet::KeychainKind::Internal, 0));
+    assert_eq!(wallet.list_unspent().len(), utxos_before);
+    assert_eq!(wallet.staged().unwrap().to_json().unwrap(), staged_before);
+    assert_eq!(
+        wallet.next_derivation_index(bdk_wallet::KeychainKind::Internal),
+        change_index_before
+    );
+
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(25_000)))
+        .fee_rate(&fee_rate)
+        .finish(&wallet)
+        .unwrap();
+    let tx = psbt.0.lock().unwrap().unsigned_tx.clone();
+    assert_eq!(tx.input.len(), 1);
+    assert_eq!(tx.input[0].previous_output.txid, preview.selected[0].txid.0);
+    assert_eq!(psbt.fee().unwrap(), preview.fee.to_sat());
+    let change_script = wallet
+        .peek_address(bdk_wallet::KeychainKind::Internal, 0)
+        .address
+        .script_pubkey();
+    assert!(tx
+        .output
+        .iter()
+        .any(|txout| txout.script_pubkey == change_script.0 && txout.value.to_sat() == change));
+}
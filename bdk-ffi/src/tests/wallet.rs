@@ -1,11 +1,25 @@
-use crate::bitcoin::{Network, NetworkKind};
+use crate::bitcoin::Script;
+use crate::bitcoin::{
+    verify_message, Address, Amount, Block, FeeRate, Network, NetworkKind, Transaction, Txid,
+};
 use crate::descriptor::Descriptor;
+use crate::error::{CalculateFeeError, CannotConnectError, LoadWithPersistError, SignerError};
 use crate::store::Persister;
+use crate::tests::{mine_block, TestWallet};
+use crate::tx_builder::TxBuilder;
+use crate::types::{
+    ChainPosition, ChangeSet, ConfirmationInfo, FullScanBatchScriptInspector,
+    FullScanScriptInspector, IndexedScript, ScanToken, SignOptions, SyncRequestBuilder,
+    UnconfirmedTx, Update,
+};
 use crate::wallet::Wallet;
 
+use bdk_wallet::chain::spk_client::SyncRequest as BdkSyncRequest;
+use bdk_wallet::chain::spk_client::SyncResponse as BdkSyncResponse;
+use bdk_wallet::chain::CheckPoint;
 use bdk_wallet::KeychainKind;
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 const EXTERNAL_DESCRIPTOR: &str = "wpkh(tprv8ZgxMBicQKsPf2qfrEygW6fdYseJDDrVnDv26PH5BHdvSuG6ecCbHqLVof9yZcMoM31z9ur3tTYbSnr1WBqbGX97CbXcmp5H6qeMpyvx35B/84h/1h/1h/0/*)";
 const INTERNAL_DESCRIPTOR: &str = "wpkh(tprv8ZgxMBicQKsPf2qfrEygW6fdYseJDDrVnDv26PH5BHdvSuG6ecCbHqLVof9yZcMoM31z9ur3tTYbSnr1WBqbGX97CbXcmp5H6qeMpyvx35B/84h/1h/1h/1/*)";
@@ -105,3 +119,1297 @@ fn test_create_two_path_wallet() {
     assert_eq!(wallet.derivation_index(KeychainKind::External), Some(0));
     assert_eq!(wallet.derivation_index(KeychainKind::Internal), Some(0));
 }
+
+#[test]
+fn test_split_two_path_descriptor() {
+    let descriptors = two_path_descriptor().to_single_descriptors().unwrap();
+    assert_eq!(descriptors.len(), 2);
+    assert!(descriptors
+        .iter()
+        .all(|descriptor| !descriptor.is_multipath()));
+    assert!(descriptors[0].to_string().contains("/0/*"));
+    assert!(descriptors[1].to_string().contains("/1/*"));
+
+    // the halves build the same wallet as the two-path descriptor
+    let split = Wallet::new(
+        descriptors[0].clone(),
+        descriptors[1].clone(),
+        Network::Signet,
+        Arc::new(Persister::new_in_memory().unwrap()),
+        25,
+    )
+    .unwrap();
+    let two_path = Wallet::create_from_two_path_descriptor(
+        two_path_descriptor(),
+        Network::Signet,
+        Arc::new(Persister::new_in_memory().unwrap()),
+        25,
+    )
+    .unwrap();
+    for keychain in [KeychainKind::External, KeychainKind::Internal] {
+        assert_eq!(
+            split.peek_address(keychain, 3).address,
+            two_path.peek_address(keychain, 3).address
+        );
+    }
+}
+
+#[test]
+fn test_transactions() {
+    let wallet = TestWallet::funded(&[10_000, 20_000]).build();
+
+    let transactions = wallet.transactions();
+    assert_eq!(transactions.len(), 2);
+
+    // unconfirmed transactions are ordered with the most recently seen first
+    let timestamps: Vec<Option<u64>> = transactions
+        .iter()
+        .map(|tx| match &tx.chain_position {
+            ChainPosition::Unconfirmed { timestamp } => *timestamp,
+            ChainPosition::Confirmed { .. } => panic!("funding transactions are unconfirmed"),
+        })
+        .collect();
+    assert_eq!(timestamps, vec![Some(2), Some(1)]);
+
+    for utxo in wallet.list_unspent() {
+        assert!(transactions
+            .iter()
+            .any(|tx| *tx.transaction.compute_txid() == *utxo.outpoint.txid));
+    }
+}
+
+#[test]
+fn test_get_tx() {
+    let wallet = TestWallet::funded(&[25_000]).build();
+    let utxo = wallet.list_unspent().pop().unwrap();
+
+    let canonical_tx = wallet.get_tx(utxo.outpoint.txid.clone()).unwrap();
+    assert!(matches!(
+        canonical_tx.chain_position,
+        ChainPosition::Unconfirmed { .. }
+    ));
+    let output = &canonical_tx.transaction.output()[utxo.outpoint.vout as usize];
+    assert_eq!(output.value.to_sat(), 25_000);
+
+    let unknown_txid = Arc::new(
+        crate::bitcoin::Txid::from_string(
+            "5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456".to_string(),
+        )
+        .unwrap(),
+    );
+    assert!(wallet.get_tx(unknown_txid).is_none());
+}
+
+#[test]
+fn test_calculate_fee() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let recipient = wallet
+        .peek_address(KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .fee_rate(&FeeRate::from_sat_per_vb(5).unwrap())
+        .finish(&wallet)
+        .unwrap();
+    let tx: Transaction = psbt.0.lock().unwrap().unsigned_tx.clone().into();
+
+    let fee = wallet.calculate_fee(&tx).unwrap();
+    assert_eq!(fee.to_sat(), psbt.fee().unwrap());
+    let fee_rate = wallet.calculate_fee_rate(&tx).unwrap();
+    assert!(fee_rate.to_sat_per_vb_floor() >= 5);
+
+    // a transaction spending an output the wallet has never seen
+    let mut foreign_tx = psbt.0.lock().unwrap().unsigned_tx.clone();
+    foreign_tx.input[0].previous_output.vout = 42;
+    let result = wallet.calculate_fee(&foreign_tx.into());
+    assert!(matches!(
+        result,
+        Err(CalculateFeeError::MissingTxOut { out_points }) if out_points.len() == 1
+    ));
+}
+
+#[test]
+fn test_list_output_includes_spent() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let funding_outpoint = wallet.list_unspent().pop().unwrap().outpoint;
+    let recipient = wallet
+        .peek_address(KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .finish(&wallet)
+        .unwrap();
+    let tx: Transaction = psbt.0.lock().unwrap().unsigned_tx.clone().into();
+    wallet.apply_unconfirmed_txs(vec![UnconfirmedTx {
+        tx: Arc::new(tx),
+        last_seen: 10,
+    }]);
+
+    assert!(wallet
+        .list_unspent()
+        .iter()
+        .all(|utxo| utxo.outpoint != funding_outpoint));
+    let spent_output = wallet
+        .list_output()
+        .into_iter()
+        .find(|output| output.outpoint == funding_outpoint)
+        .expect("spent outputs are still listed");
+    assert!(spent_output.is_spent);
+    assert_eq!(spent_output.txout.value.to_sat(), 50_000);
+}
+
+#[test]
+fn test_cancel_tx() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let recipient = wallet
+        .peek_address(KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+    let build = || -> bdk_wallet::bitcoin::Transaction {
+        let psbt = TxBuilder::new()
+            .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+            .finish(&wallet)
+            .unwrap();
+        let tx = psbt.0.lock().unwrap().unsigned_tx.clone();
+        tx
+    };
+    let change_script = |tx: &bdk_wallet::bitcoin::Transaction| {
+        tx.output
+            .iter()
+            .find(|output| output.script_pubkey != recipient.0)
+            .unwrap()
+            .script_pubkey
+            .clone()
+    };
+
+    let draft = build();
+    let second_draft = build();
+    // without cancelling, the next build avoids the change address of the first draft
+    assert_ne!(change_script(&draft), change_script(&second_draft));
+
+    wallet.cancel_tx(&second_draft.clone().into());
+    wallet.cancel_tx(&draft.clone().into());
+    let rebuilt = build();
+    assert_eq!(
+        rebuilt.input[0].previous_output,
+        draft.input[0].previous_output
+    );
+    assert_eq!(change_script(&rebuilt), change_script(&draft));
+}
+
+#[test]
+fn test_balance_for_keychain() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let recipient = wallet
+        .peek_address(KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+
+    // pay ourselves so that both keychains hold an output
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .finish(&wallet)
+        .unwrap();
+    let tx: Transaction = psbt.0.lock().unwrap().unsigned_tx.clone().into();
+    wallet.apply_unconfirmed_txs(vec![UnconfirmedTx {
+        tx: Arc::new(tx),
+        last_seen: 10,
+    }]);
+
+    let external = wallet.balance_for_keychain(KeychainKind::External);
+    let internal = wallet.balance_for_keychain(KeychainKind::Internal);
+    let total = wallet.balance();
+
+    assert_eq!(external.untrusted_pending.to_sat(), 10_000);
+    assert_eq!(external.trusted_pending.to_sat(), 0);
+    assert_eq!(
+        internal.trusted_pending.to_sat(),
+        50_000 - 10_000 - psbt.fee().unwrap()
+    );
+    assert_eq!(internal.untrusted_pending.to_sat(), 0);
+    assert_eq!(
+        external.total.to_sat() + internal.total.to_sat(),
+        total.total.to_sat()
+    );
+    assert_eq!(
+        external.untrusted_pending.to_sat() + internal.untrusted_pending.to_sat(),
+        total.untrusted_pending.to_sat()
+    );
+    assert_eq!(
+        external.trusted_pending.to_sat() + internal.trusted_pending.to_sat(),
+        total.trusted_pending.to_sat()
+    );
+}
+
+#[test]
+fn test_peek_address() {
+    let wallet = build_wallet();
+
+    let first_peek = wallet.peek_address(KeychainKind::External, 5);
+    let second_peek = wallet.peek_address(KeychainKind::External, 5);
+
+    assert_eq!(first_peek.index, 5);
+    assert_eq!(first_peek.keychain, KeychainKind::External);
+    assert_eq!(
+        first_peek.address.to_string(),
+        second_peek.address.to_string()
+    );
+    assert_eq!(wallet.derivation_index(KeychainKind::External), None);
+    assert_eq!(
+        wallet
+            .reveal_next_address(KeychainKind::External)
+            .address
+            .to_string(),
+        EXPECTED_FIRST_ADDRESS
+    );
+}
+
+#[test]
+fn test_reveal_addresses_to_indices() {
+    let wallet = build_wallet();
+
+    let revealed = wallet.reveal_addresses_to(KeychainKind::Internal, 10);
+    let indices: Vec<u32> = revealed.iter().map(|info| info.index).collect();
+    assert_eq!(indices, (0..=10).collect::<Vec<u32>>());
+    for info in &revealed {
+        assert_eq!(info.keychain, KeychainKind::Internal);
+        assert_eq!(
+            info.address,
+            wallet
+                .peek_address(KeychainKind::Internal, info.index)
+                .address
+        );
+    }
+
+    // only the addresses past the last revealed index are returned
+    let indices: Vec<u32> = wallet
+        .reveal_addresses_to(KeychainKind::Internal, 12)
+        .iter()
+        .map(|info| info.index)
+        .collect();
+    assert_eq!(indices, vec![11, 12]);
+}
+
+#[test]
+fn test_mark_used() {
+    let wallet = build_wallet();
+    wallet.reveal_addresses_to(KeychainKind::External, 3);
+
+    for index in 0..=3 {
+        assert!(wallet.mark_used(KeychainKind::External, index));
+    }
+    // marking an index twice does not change anything
+    assert!(!wallet.mark_used(KeychainKind::External, 3));
+
+    // every revealed address is used, so a new one is revealed
+    assert_eq!(wallet.next_unused_address(KeychainKind::External).index, 4);
+
+    assert!(wallet.unmark_used(KeychainKind::External, 3));
+    assert_eq!(wallet.next_unused_address(KeychainKind::External).index, 3);
+}
+
+#[test]
+fn test_next_unused_address_is_stable_until_used() {
+    let wallet = build_wallet();
+    let first = wallet.next_unused_address(KeychainKind::External);
+    assert_eq!(first.index, 0);
+    assert_eq!(first.address.to_string(), EXPECTED_FIRST_ADDRESS);
+
+    // asking again without receiving funds does not burn an index
+    let again = wallet.next_unused_address(KeychainKind::External);
+    assert_eq!(again.index, 0);
+    assert_eq!(again.address.to_string(), first.address.to_string());
+    assert_eq!(wallet.derivation_index(KeychainKind::External), Some(0));
+
+    let tx = bdk_wallet::bitcoin::Transaction {
+        version: bdk_wallet::bitcoin::transaction::Version::TWO,
+        lock_time: bdk_wallet::bitcoin::absolute::LockTime::ZERO,
+        input: vec![bdk_wallet::bitcoin::TxIn {
+            previous_output: bdk_wallet::bitcoin::OutPoint {
+                txid: bdk_wallet::bitcoin::hashes::Hash::from_byte_array([8; 32]),
+                vout: 0,
+            },
+            ..Default::default()
+        }],
+        output: vec![bdk_wallet::bitcoin::TxOut {
+            value: bdk_wallet::bitcoin::Amount::from_sat(15_000),
+            script_pubkey: first.address.script_pubkey().0.clone(),
+        }],
+    };
+    wallet.apply_unconfirmed_txs(vec![UnconfirmedTx {
+        tx: Arc::new(tx.into()),
+        last_seen: 1,
+    }]);
+
+    // once the address received funds the next one is handed out
+    let next = wallet.next_unused_address(KeychainKind::External);
+    assert_eq!(next.index, 1);
+    assert_ne!(next.address.to_string(), first.address.to_string());
+}
+
+#[test]
+fn test_changeset_json_round_trip() {
+    let persister = Arc::new(Persister::new_in_memory().unwrap());
+    let wallet = TestWallet::funded(&[10_000, 20_000])
+        .persister(persister.clone())
+        .build();
+    wallet.reveal_addresses_to(KeychainKind::Internal, 2);
+
+    // the app keeps what it stored when the wallet was created and adds the staged changes
+    let stored =
+        Arc::new(ChangeSet::from_json(persister.export_changeset_json().unwrap()).unwrap());
+    let staged = wallet.take_staged().unwrap();
+    assert!(wallet.take_staged().is_none());
+    assert!(staged.network().is_none());
+    let json = ChangeSet::from_merge(stored, staged).to_json().unwrap();
+
+    let loaded =
+        Wallet::load_from_changeset(external_descriptor(), internal_descriptor(), json, 25)
+            .unwrap();
+
+    assert_eq!(loaded.balance().total.to_sat(), 30_000);
+    assert_eq!(loaded.transactions().len(), 2);
+    for keychain in [KeychainKind::External, KeychainKind::Internal] {
+        assert_eq!(
+            loaded.next_derivation_index(keychain),
+            wallet.next_derivation_index(keychain)
+        );
+    }
+
+    assert!(ChangeSet::from_json("not json".to_string()).is_err());
+    assert!(matches!(
+        Wallet::load_from_changeset(
+            external_descriptor(),
+            internal_descriptor(),
+            "not json".to_string(),
+            25
+        ),
+        Err(LoadWithPersistError::InvalidChangeSet { .. })
+    ));
+}
+
+#[derive(Default)]
+struct CountingInspector {
+    calls: Mutex<usize>,
+}
+
+impl FullScanScriptInspector for CountingInspector {
+    fn inspect(&self, _: KeychainKind, _: u32, _: Arc<Script>) {
+        *self.calls.lock().unwrap() += 1;
+    }
+}
+
+#[derive(Default)]
+struct BatchRecorder {
+    batches: Mutex<Vec<(KeychainKind, Vec<u32>)>>,
+}
+
+impl FullScanBatchScriptInspector for BatchRecorder {
+    fn inspect_batch(&self, keychain: KeychainKind, scripts: Vec<IndexedScript>) {
+        let indices = scripts.iter().map(|script| script.index).collect();
+        self.batches.lock().unwrap().push((keychain, indices));
+    }
+}
+
+#[test]
+fn test_batched_full_scan_inspector() {
+    let wallet = build_wallet();
+
+    let scan_25_per_keychain = |request: Arc<crate::types::FullScanRequest>| {
+        let mut request = request.0.lock().unwrap().take().unwrap();
+        for keychain in request.keychains() {
+            assert_eq!(request.iter_spks(keychain).take(25).count(), 25);
+        }
+    };
+
+    let inspector = Arc::new(CountingInspector::default());
+    scan_25_per_keychain(
+        wallet
+            .start_full_scan()
+            .inspect_spks_for_all_keychains(inspector.clone())
+            .unwrap()
+            .build()
+            .unwrap(),
+    );
+    assert_eq!(*inspector.calls.lock().unwrap(), 50);
+
+    let recorder = Arc::new(BatchRecorder::default());
+    scan_25_per_keychain(
+        wallet
+            .start_full_scan()
+            .inspect_spks_for_all_keychains_batched(recorder.clone(), 10)
+            .unwrap()
+            .build()
+            .unwrap(),
+    );
+    let batches = recorder.batches.lock().unwrap();
+    let sizes: Vec<(KeychainKind, usize)> = batches
+        .iter()
+        .map(|(keychain, indices)| (*keychain, indices.len()))
+        .collect();
+    assert_eq!(
+        sizes,
+        vec![
+            (KeychainKind::External, 10),
+            (KeychainKind::External, 10),
+            (KeychainKind::External, 5),
+            (KeychainKind::Internal, 10),
+            (KeychainKind::Internal, 10),
+            (KeychainKind::Internal, 5),
+        ]
+    );
+    assert_eq!(batches[0].1, (0..10).collect::<Vec<u32>>());
+}
+
+#[test]
+fn test_sign_message() {
+    let wallet = build_wallet();
+    let address = wallet.reveal_next_address(KeychainKind::External).address;
+    let message = "proof of ownership".to_string();
+
+    let signature = wallet
+        .sign_message(message.clone(), address.clone())
+        .unwrap();
+
+    assert!(address.verify_message(message.clone(), signature.clone()));
+    assert!(verify_message(
+        address.clone(),
+        message.clone(),
+        signature.clone()
+    ));
+    assert!(!address.verify_message("another message".to_string(), signature.clone()));
+    assert!(!address.verify_message(message.clone(), "not base64".to_string()));
+
+    let other_address = wallet.reveal_next_address(KeychainKind::External).address;
+    assert!(!other_address.verify_message(message.clone(), signature));
+
+    let foreign_address = Arc::new(
+        Address::new(
+            "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".to_string(),
+            Network::Signet,
+        )
+        .unwrap(),
+    );
+    assert!(matches!(
+        wallet.sign_message(message, foreign_address),
+        Err(SignerError::MissingKey)
+    ));
+}
+
+#[test]
+fn test_can_rbf() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let funding_txid = wallet.list_unspent()[0].outpoint.txid.clone();
+    let recipient = wallet
+        .peek_address(KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .finish(&wallet)
+        .unwrap();
+    assert!(wallet.sign(psbt.clone(), None).unwrap());
+    let spend = psbt.extract_tx().unwrap();
+    wallet.apply_unconfirmed_txs(vec![UnconfirmedTx {
+        tx: spend.clone(),
+        last_seen: 10,
+    }]);
+
+    assert!(wallet.can_rbf(spend.compute_txid()));
+    // the funding transaction uses final sequence numbers
+    assert!(!wallet.can_rbf(funding_txid));
+    assert!(!wallet.can_rbf(Arc::new(
+        Txid::from_string(
+            "5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456".to_string()
+        )
+        .unwrap()
+    )));
+}
+
+#[test]
+fn test_list_unspent_confirmed() {
+    let wallet = TestWallet::funded(&[10_000, 20_000, 40_000]).build();
+    let funding_tx = |amount: u64| -> bdk_wallet::bitcoin::Transaction {
+        let utxo = wallet
+            .list_unspent()
+            .into_iter()
+            .find(|utxo| utxo.txout.value.to_sat() == amount)
+            .unwrap();
+        let tx = wallet.get_tx(utxo.outpoint.txid).unwrap().transaction;
+        tx.as_ref().into()
+    };
+    let coinbase = bdk_wallet::bitcoin::Transaction {
+        version: bdk_wallet::bitcoin::transaction::Version::TWO,
+        lock_time: bdk_wallet::bitcoin::absolute::LockTime::ZERO,
+        input: vec![bdk_wallet::bitcoin::TxIn::default()],
+        output: vec![bdk_wallet::bitcoin::TxOut {
+            value: bdk_wallet::bitcoin::Amount::from_sat(80_000),
+            script_pubkey: wallet
+                .reveal_next_address(KeychainKind::External)
+                .address
+                .script_pubkey()
+                .0
+                .clone(),
+        }],
+    };
+    assert!(coinbase.is_coinbase());
+
+    // 10,000 confirms at height 1, 20,000 and the coinbase at height 4, 40,000 stays unconfirmed
+    mine_block(&wallet, vec![funding_tx(10_000)]);
+    mine_block(&wallet, vec![]);
+    mine_block(&wallet, vec![]);
+    mine_block(&wallet, vec![funding_tx(20_000), coinbase]);
+
+    let amounts = |min_confirmations: u32, current_height: u32| {
+        let mut amounts: Vec<u64> = wallet
+            .list_unspent_confirmed(min_confirmations, current_height)
+            .iter()
+            .map(|utxo| utxo.txout.value.to_sat())
+            .collect();
+        amounts.sort();
+        amounts
+    };
+    assert_eq!(amounts(0, 4), vec![10_000, 20_000, 40_000]);
+    assert_eq!(amounts(1, 4), vec![10_000, 20_000]);
+    assert_eq!(amounts(2, 4), vec![10_000]);
+    assert_eq!(amounts(5, 4), Vec::<u64>::new());
+    assert_eq!(amounts(5, 8), vec![10_000, 20_000]);
+    assert_eq!(amounts(100, 103), vec![10_000, 20_000, 80_000]);
+}
+
+#[test]
+fn test_sign_with_non_default_sighash() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let recipient = wallet
+        .peek_address(KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+    let build = || {
+        TxBuilder::new()
+            .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+            .sighash("SIGHASH_SINGLE|SIGHASH_ANYONECANPAY".to_string())
+            .unwrap()
+            .finish(&wallet)
+            .unwrap()
+    };
+    let sign_options = |allow_all_sighashes: bool| SignOptions {
+        trust_witness_utxo: false,
+        assume_height: None,
+        allow_all_sighashes,
+        try_finalize: true,
+        sign_with_tap_internal_key: true,
+        allow_grinding: true,
+    };
+
+    assert!(matches!(
+        wallet.sign(build(), Some(sign_options(false))),
+        Err(SignerError::NonStandardSighash)
+    ));
+
+    let psbt = build();
+    assert!(wallet.sign(psbt.clone(), Some(sign_options(true))).unwrap());
+    // once finalized the signature lives in the witness, ending with the sighash byte
+    let witness = psbt.0.lock().unwrap().inputs[0]
+        .final_script_witness
+        .clone()
+        .unwrap();
+    assert_eq!(witness.nth(0).unwrap().last(), Some(&0x83));
+}
+
+#[test]
+fn test_finalize_externally_signed_psbt() {
+    let signer = TestWallet::funded(&[50_000]).build();
+    let watch_only = TestWallet::funded(&[50_000])
+        .descriptors(
+            &signer.public_descriptor(KeychainKind::External),
+            &signer.public_descriptor(KeychainKind::Internal),
+        )
+        .build();
+    let recipient = watch_only
+        .peek_address(KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .finish(&watch_only)
+        .unwrap();
+
+    // the watch-only wallet holds no keys, so signing adds nothing
+    assert!(!watch_only.sign(psbt.clone(), None).unwrap());
+    assert!(!watch_only.finalize_psbt(psbt.clone(), None).unwrap());
+
+    // an external signer adds its signature without finalizing
+    let sign_options = SignOptions {
+        trust_witness_utxo: false,
+        assume_height: None,
+        allow_all_sighashes: false,
+        try_finalize: false,
+        sign_with_tap_internal_key: true,
+        allow_grinding: true,
+    };
+    signer.sign(psbt.clone(), Some(sign_options)).unwrap();
+    assert_eq!(psbt.input()[0].partial_sigs.len(), 1);
+    assert_eq!(psbt.finalized_inputs(), vec![false]);
+
+    assert!(watch_only.finalize_psbt(psbt.clone(), None).unwrap());
+    assert_eq!(psbt.finalized_inputs(), vec![true]);
+    assert!(psbt.extract_tx().is_ok());
+}
+
+#[test]
+fn test_public_descriptor() {
+    let wallet = build_wallet();
+
+    for keychain in [KeychainKind::External, KeychainKind::Internal] {
+        let descriptor = wallet.public_descriptor(keychain);
+        assert!(descriptor.starts_with("wpkh("));
+        assert!(descriptor.contains("tpub"));
+        assert!(!descriptor.contains("tprv"));
+        assert!(descriptor.ends_with(&format!("#{}", wallet.descriptor_checksum(keychain))));
+    }
+}
+
+#[test]
+fn test_signers() {
+    let wallet = build_wallet();
+    let signers = wallet.signers(KeychainKind::External);
+    assert_eq!(signers.len(), 1);
+    assert!(signers[0].has_secret);
+    assert_eq!(signers[0].fingerprint.len(), 8);
+
+    // a watch-only copy knows the same key but cannot sign for it
+    let watch_only = Wallet::new(
+        Arc::new(
+            Descriptor::new(
+                wallet.public_descriptor(KeychainKind::External),
+                NetworkKind::Test,
+            )
+            .unwrap(),
+        ),
+        Arc::new(
+            Descriptor::new(
+                wallet.public_descriptor(KeychainKind::Internal),
+                NetworkKind::Test,
+            )
+            .unwrap(),
+        ),
+        Network::Signet,
+        Arc::new(Persister::new_in_memory().unwrap()),
+        25,
+    )
+    .unwrap();
+    let watch_only_signers = watch_only.signers(KeychainKind::External);
+    assert_eq!(watch_only_signers.len(), 1);
+    assert_eq!(watch_only_signers[0].fingerprint, signers[0].fingerprint);
+    assert!(!watch_only_signers[0].has_secret);
+}
+
+#[test]
+fn test_derivation_indices_advance() {
+    let wallet = build_wallet();
+    assert_eq!(wallet.derivation_index(KeychainKind::Internal), None);
+    assert_eq!(wallet.next_derivation_index(KeychainKind::Internal), 0);
+
+    for expected in 0..3 {
+        wallet.reveal_next_address(KeychainKind::Internal);
+        assert_eq!(
+            wallet.derivation_index(KeychainKind::Internal),
+            Some(expected)
+        );
+        assert_eq!(
+            wallet.next_derivation_index(KeychainKind::Internal),
+            expected + 1
+        );
+    }
+
+    // peeking does not reveal, and the external keychain is tracked separately
+    wallet.peek_address(KeychainKind::Internal, 10);
+    assert_eq!(wallet.derivation_index(KeychainKind::Internal), Some(2));
+    assert_eq!(wallet.derivation_index(KeychainKind::External), None);
+}
+
+#[test]
+fn test_merge_updates() {
+    let wallet = build_wallet();
+    let payment = |vout: u32, amount: u64| {
+        let script_pubkey = wallet
+            .reveal_next_address(KeychainKind::External)
+            .address
+            .script_pubkey();
+        Arc::new(bdk_wallet::bitcoin::Transaction {
+            version: bdk_wallet::bitcoin::transaction::Version::TWO,
+            lock_time: bdk_wallet::bitcoin::absolute::LockTime::ZERO,
+            input: vec![bdk_wallet::bitcoin::TxIn {
+                previous_output: bdk_wallet::bitcoin::OutPoint {
+                    txid: bdk_wallet::bitcoin::hashes::Hash::from_byte_array([2; 32]),
+                    vout,
+                },
+                ..Default::default()
+            }],
+            output: vec![bdk_wallet::bitcoin::TxOut {
+                value: bdk_wallet::bitcoin::Amount::from_sat(amount),
+                script_pubkey: script_pubkey.0.clone(),
+            }],
+        })
+    };
+    let confirmed_tx = payment(0, 10_000);
+    let mempool_tx = payment(1, 20_000);
+    let tip = wallet.get_wallet().latest_checkpoint();
+
+    // a full scan that found a transaction confirmed in the genesis block
+    let mut historical = bdk_wallet::Update::default();
+    historical
+        .last_active_indices
+        .insert(KeychainKind::External, 0);
+    historical.tx_update.txs.push(confirmed_tx.clone());
+    historical.tx_update.anchors.insert((
+        bdk_wallet::chain::ConfirmationBlockTime {
+            block_id: tip.block_id(),
+            confirmation_time: 0,
+        },
+        confirmed_tx.compute_txid(),
+    ));
+    historical.chain = Some(tip);
+
+    // a later mempool sync that saw an unconfirmed payment
+    let mut mempool = bdk_wallet::Update::default();
+    mempool
+        .last_active_indices
+        .insert(KeychainKind::External, 1);
+    mempool.tx_update.txs.push(mempool_tx.clone());
+    mempool
+        .tx_update
+        .seen_ats
+        .insert((mempool_tx.compute_txid(), 100));
+
+    let merged = Update(historical).merge(Arc::new(Update(mempool))).unwrap();
+    assert_eq!(
+        merged.0.last_active_indices.get(&KeychainKind::External),
+        Some(&1)
+    );
+    assert!(merged.0.chain.is_some());
+    assert_eq!(merged.0.tx_update.txs.len(), 2);
+
+    wallet.apply_update(merged).unwrap();
+    let balance = wallet.balance();
+    assert_eq!(balance.confirmed.to_sat(), 10_000);
+    assert_eq!(balance.untrusted_pending.to_sat(), 20_000);
+}
+
+#[test]
+fn test_merge_updates_keeps_blocks_of_both_chains() {
+    let wallet = build_wallet();
+    let script_pubkey = wallet
+        .reveal_next_address(KeychainKind::External)
+        .address
+        .script_pubkey();
+    let confirmed_tx = Arc::new(bdk_wallet::bitcoin::Transaction {
+        version: bdk_wallet::bitcoin::transaction::Version::TWO,
+        lock_time: bdk_wallet::bitcoin::absolute::LockTime::ZERO,
+        input: vec![bdk_wallet::bitcoin::TxIn {
+            previous_output: bdk_wallet::bitcoin::OutPoint {
+                txid: bdk_wallet::bitcoin::hashes::Hash::from_byte_array([2; 32]),
+                vout: 0,
+            },
+            ..Default::default()
+        }],
+        output: vec![bdk_wallet::bitcoin::TxOut {
+            value: bdk_wallet::bitcoin::Amount::from_sat(10_000),
+            script_pubkey: script_pubkey.0.clone(),
+        }],
+    });
+    let block = |height: u32, byte: u8| bdk_wallet::chain::BlockId {
+        height,
+        hash: bdk_wallet::bitcoin::hashes::Hash::from_byte_array([byte; 32]),
+    };
+    let genesis = wallet.get_wallet().latest_checkpoint();
+
+    // a full scan that found a transaction confirmed in a historical block
+    let mut historical = bdk_wallet::Update::default();
+    historical.tx_update.txs.push(confirmed_tx.clone());
+    historical.tx_update.anchors.insert((
+        bdk_wallet::chain::ConfirmationBlockTime {
+            block_id: block(1, 1),
+            confirmation_time: 0,
+        },
+        confirmed_tx.compute_txid(),
+    ));
+    historical.chain = Some(
+        genesis
+            .clone()
+            .push(block(1, 1))
+            .unwrap()
+            .push(block(2, 2))
+            .unwrap(),
+    );
+
+    // a later sync whose chain only holds the agreement point and the new tip
+    let mut later = bdk_wallet::Update::default();
+    later.chain = Some(genesis.clone().push(block(3, 3)).unwrap());
+
+    let merged = Update(historical.clone())
+        .merge(Arc::new(Update(later)))
+        .unwrap();
+    let heights: Vec<u32> = merged
+        .0
+        .chain
+        .as_ref()
+        .unwrap()
+        .iter()
+        .map(|checkpoint| checkpoint.height())
+        .collect();
+    assert_eq!(heights, vec![3, 2, 1, 0]);
+
+    wallet.apply_update(merged).unwrap();
+    assert_eq!(wallet.latest_checkpoint().height, 3);
+    assert_eq!(wallet.balance().confirmed.to_sat(), 10_000);
+
+    // a chain holding another block at height 2 cannot be merged
+    let mut conflicting = bdk_wallet::Update::default();
+    conflicting.chain = Some(
+        genesis
+            .push(block(2, 9))
+            .unwrap()
+            .push(block(3, 3))
+            .unwrap(),
+    );
+    let result = Update(historical).merge(Arc::new(Update(conflicting)));
+    assert!(matches!(
+        result,
+        Err(CannotConnectError::Include { height: 2 })
+    ));
+}
+
+#[test]
+fn test_sync_in_chunks_merges_chain_updates() {
+    let wallet = TestWallet::funded(&[10_000, 20_000]).build();
+    let block = |height: u32, byte: u8| bdk_wallet::chain::BlockId {
+        height,
+        hash: bdk_wallet::bitcoin::hashes::Hash::from_byte_array([byte; 32]),
+    };
+    let genesis = wallet.get_wallet().latest_checkpoint();
+    let request = || {
+        let request = wallet.start_sync_with_revealed_spks().build().unwrap();
+        request.0.lock().unwrap().take().unwrap()
+    };
+    // each chunk of one script sees the chain the server reports at that time
+    let sync_against = |chains: Vec<CheckPoint>| {
+        let mut chains = chains.into_iter();
+        move |_chunk: BdkSyncRequest| -> Result<BdkSyncResponse, CannotConnectError> {
+            Ok(BdkSyncResponse {
+                chain_update: chains.next(),
+                ..Default::default()
+            })
+        }
+    };
+
+    // a block mined between the chunks extends the chain
+    let first = genesis.clone().push(block(1, 1)).unwrap();
+    let second = first.clone().push(block(2, 2)).unwrap();
+    let response = ScanToken::new()
+        .sync_in_chunks(request(), 1, sync_against(vec![first.clone(), second]))
+        .unwrap()
+        .unwrap();
+    let heights: Vec<u32> = response
+        .chain_update
+        .unwrap()
+        .iter()
+        .map(|checkpoint| checkpoint.height())
+        .collect();
+    assert_eq!(heights, vec![2, 1, 0]);
+
+    // a reorg between the chunks is reported instead of dropping either tip
+    let reorged = genesis
+        .push(block(1, 9))
+        .unwrap()
+        .push(block(2, 2))
+        .unwrap();
+    let result = ScanToken::new().sync_in_chunks(request(), 1, sync_against(vec![first, reorged]));
+    assert!(matches!(
+        result,
+        Err(CannotConnectError::Include { height: 1 })
+    ));
+}
+
+#[test]
+fn test_latest_checkpoint_advances_after_update() {
+    let wallet = build_wallet();
+    let genesis = wallet.latest_checkpoint();
+    assert_eq!(genesis.height, 0);
+    assert_eq!(
+        genesis.hash.to_string(),
+        "00000008819873e925422c1ff0f99f7cc9bbb232af63a077a480a3633bee1ef6"
+    );
+
+    let tip = wallet.get_wallet().latest_checkpoint();
+    let block_id = bdk_wallet::chain::BlockId {
+        height: 1,
+        hash: bdk_wallet::bitcoin::hashes::Hash::from_byte_array([7; 32]),
+    };
+    let mut update = bdk_wallet::Update::default();
+    update.chain = Some(tip.push(block_id).unwrap());
+    wallet.apply_update(Arc::new(Update(update))).unwrap();
+
+    let tip = wallet.latest_checkpoint();
+    assert_eq!(tip.height, 1);
+    assert_eq!(tip.hash.0, block_id.hash);
+}
+
+#[test]
+fn test_apply_block() {
+    use bdk_wallet::bitcoin::{block, hashes::Hash, CompactTarget, TxMerkleNode};
+
+    let wallet = build_wallet();
+    let address = wallet.reveal_next_address(KeychainKind::External).address;
+    let tx = bdk_wallet::bitcoin::Transaction {
+        version: bdk_wallet::bitcoin::transaction::Version::TWO,
+        lock_time: bdk_wallet::bitcoin::absolute::LockTime::ZERO,
+        input: vec![bdk_wallet::bitcoin::TxIn {
+            previous_output: bdk_wallet::bitcoin::OutPoint {
+                txid: Hash::from_byte_array([5; 32]),
+                vout: 0,
+            },
+            ..Default::default()
+        }],
+        output: vec![bdk_wallet::bitcoin::TxOut {
+            value: bdk_wallet::bitcoin::Amount::from_sat(40_000),
+            script_pubkey: address.script_pubkey().0.clone(),
+        }],
+    };
+    let next_block = |prev_blockhash, time, txdata| bdk_wallet::bitcoin::Block {
+        header: block::Header {
+            version: block::Version::TWO,
+            prev_blockhash,
+            merkle_root: TxMerkleNode::all_zeros(),
+            time,
+            bits: CompactTarget::from_consensus(0x207fffff),
+            nonce: 0,
+        },
+        txdata,
+    };
+
+    let genesis = wallet.get_wallet().latest_checkpoint().hash();
+    let first = next_block(genesis, 1, vec![tx.clone()]);
+    let second = next_block(first.block_hash(), 2, vec![]);
+    // blocks arrive as raw bytes, as returned by the getblock RPC of a node
+    let parse = |block: &bdk_wallet::bitcoin::Block| {
+        Arc::new(Block::from_bytes(bdk_wallet::bitcoin::consensus::serialize(block)).unwrap())
+    };
+    wallet.apply_block(parse(&first), 1).unwrap();
+    wallet.apply_block(parse(&second), 2).unwrap();
+
+    assert_eq!(wallet.balance().confirmed.to_sat(), 40_000);
+    let tip = wallet.latest_checkpoint();
+    assert_eq!(tip.height, 2);
+    assert_eq!(tip.hash.0, second.block_hash());
+    match wallet.transactions()[0].chain_position {
+        ChainPosition::Confirmed {
+            ref confirmation_block_time,
+            ..
+        } => {
+            assert_eq!(confirmation_block_time.block_id.height, 1);
+            assert_eq!(confirmation_block_time.block_id.hash.0, first.block_hash());
+        }
+        _ => panic!("the transaction should be confirmed in the first block"),
+    }
+
+    // a block whose parent the wallet has never seen leaves a gap
+    let unknown_parent = Hash::from_byte_array([9; 32]);
+    let orphan = next_block(unknown_parent, 5, vec![]);
+    assert!(wallet.apply_block(parse(&orphan), 5).is_err());
+    assert_eq!(wallet.latest_checkpoint().height, 2);
+}
+
+#[test]
+fn test_lookahead_discovers_skipped_addresses() {
+    let payment_to_index_30 = |wallet: &Wallet| {
+        let address = wallet.peek_address(KeychainKind::External, 30).address;
+        bdk_wallet::bitcoin::Transaction {
+            version: bdk_wallet::bitcoin::transaction::Version::TWO,
+            lock_time: bdk_wallet::bitcoin::absolute::LockTime::ZERO,
+            input: vec![bdk_wallet::bitcoin::TxIn {
+                previous_output: bdk_wallet::bitcoin::OutPoint {
+                    txid: bdk_wallet::bitcoin::hashes::Hash::from_byte_array([6; 32]),
+                    vout: 0,
+                },
+                ..Default::default()
+            }],
+            output: vec![bdk_wallet::bitcoin::TxOut {
+                value: bdk_wallet::bitcoin::Amount::from_sat(30_000),
+                script_pubkey: address.script_pubkey().0.clone(),
+            }],
+        }
+    };
+
+    for (lookahead, discovered) in [(25, false), (30, false), (31, true), (40, true)] {
+        let wallet = Wallet::new(
+            external_descriptor(),
+            internal_descriptor(),
+            Network::Signet,
+            Arc::new(Persister::new_in_memory().unwrap()),
+            lookahead,
+        )
+        .unwrap();
+        let tx = payment_to_index_30(&wallet);
+        wallet.apply_unconfirmed_txs(vec![UnconfirmedTx {
+            tx: Arc::new(tx.into()),
+            last_seen: 1,
+        }]);
+
+        assert_eq!(
+            wallet.balance().untrusted_pending.to_sat() == 30_000,
+            discovered,
+            "lookahead {lookahead}"
+        );
+        assert_eq!(
+            wallet.derivation_index(KeychainKind::External),
+            discovered.then_some(30),
+            "lookahead {lookahead}"
+        );
+    }
+}
+
+#[test]
+fn test_network() {
+    for network in [Network::Testnet, Network::Signet, Network::Regtest] {
+        let wallet = Wallet::new(
+            external_descriptor(),
+            internal_descriptor(),
+            network,
+            Arc::new(Persister::new_in_memory().unwrap()),
+            25,
+        )
+        .unwrap();
+        assert_eq!(wallet.network(), network);
+    }
+}
+
+#[test]
+fn test_sync_revealed_spks_up_to() {
+    let wallet = Arc::new(build_wallet());
+    wallet.reveal_addresses_to(KeychainKind::External, 9);
+
+    let request =
+        SyncRequestBuilder::revealed_spks_up_to(wallet.clone(), KeychainKind::External, 3)
+            .build()
+            .unwrap();
+    let mut request = request.0.lock().unwrap().take().unwrap();
+    let expected: Vec<_> = (7..=9)
+        .rev()
+        .map(|index| {
+            wallet
+                .peek_address(KeychainKind::External, index)
+                .address
+                .script_pubkey()
+                .0
+                .clone()
+        })
+        .collect();
+    assert_eq!(
+        request
+            .iter_spks_with_expected_txids()
+            .map(|s| s.spk)
+            .collect::<Vec<_>>(),
+        expected
+    );
+
+    let request = SyncRequestBuilder::revealed_spks_up_to(wallet, KeychainKind::Internal, 3)
+        .build()
+        .unwrap();
+    let mut request = request.0.lock().unwrap().take().unwrap();
+    assert_eq!(request.iter_spks_with_expected_txids().count(), 0);
+}
+
+#[test]
+fn test_sync_revealed_spks_up_to_expects_known_txids() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let txid = wallet.transactions()[0].transaction.compute_txid();
+
+    let request =
+        SyncRequestBuilder::revealed_spks_up_to(wallet.clone(), KeychainKind::External, 1)
+            .build()
+            .unwrap();
+    let mut request = request.0.lock().unwrap().take().unwrap();
+    let spks: Vec<_> = request.iter_spks_with_expected_txids().collect();
+    assert_eq!(spks.len(), 1);
+    assert!(spks[0].expected_txids.contains(&txid.0));
+}
+
+#[test]
+fn test_sync_evicts_expected_unconfirmed_txids() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let txid = wallet.transactions()[0].transaction.compute_txid();
+    let spk = wallet
+        .peek_address(KeychainKind::External, 0)
+        .address
+        .script_pubkey()
+        .0
+        .clone();
+
+    let request =
+        SyncRequestBuilder::revealed_spks_up_to(wallet.clone(), KeychainKind::External, 1)
+            .expected_unconfirmed_txids(wallet.clone(), vec![txid.clone()])
+            .unwrap()
+            .build()
+            .unwrap();
+    let mut request = request.0.lock().unwrap().take().unwrap();
+    let spks: Vec<_> = request.iter_spks_with_expected_txids().collect();
+    assert_eq!(spks.len(), 1);
+    assert_eq!(spks[0].spk, spk);
+    assert!(spks[0].expected_txids.contains(&txid.0));
+
+    // the chain source no longer lists the transaction in the script's history and reports it
+    // as evicted
+    let mut update = bdk_wallet::Update::default();
+    update.tx_update.evicted_ats.insert((txid.0, 100));
+    wallet.apply_update(Arc::new(Update(update))).unwrap();
+    assert!(wallet.transactions().is_empty());
+    assert_eq!(wallet.balance().total.to_sat(), 0);
+}
+
+#[test]
+fn test_apply_unconfirmed_txs() {
+    let wallet = build_wallet();
+    assert_eq!(wallet.balance().untrusted_pending.to_sat(), 0);
+
+    let address = wallet.reveal_next_address(KeychainKind::External).address;
+    let tx = bdk_wallet::bitcoin::Transaction {
+        version: bdk_wallet::bitcoin::transaction::Version::TWO,
+        lock_time: bdk_wallet::bitcoin::absolute::LockTime::ZERO,
+        input: vec![bdk_wallet::bitcoin::TxIn {
+            previous_output: bdk_wallet::bitcoin::OutPoint {
+                txid: bdk_wallet::bitcoin::hashes::Hash::from_byte_array([3; 32]),
+                vout: 0,
+            },
+            ..Default::default()
+        }],
+        output: vec![bdk_wallet::bitcoin::TxOut {
+            value: bdk_wallet::bitcoin::Amount::from_sat(30_000),
+            script_pubkey: address.script_pubkey().0.clone(),
+        }],
+    };
+    wallet.apply_unconfirmed_txs(vec![UnconfirmedTx {
+        tx: Arc::new(tx.into()),
+        last_seen: 1,
+    }]);
+
+    let balance = wallet.balance();
+    assert_eq!(balance.untrusted_pending.to_sat(), 30_000);
+    assert_eq!(balance.confirmed.to_sat(), 0);
+    assert_eq!(balance.total.to_sat(), 30_000);
+}
+
+#[test]
+fn test_insert_txout_enables_fee_calculation() {
+    let wallet = TestWallet::funded(&[50_000]).build();
+    let recipient = wallet
+        .peek_address(KeychainKind::External, 10)
+        .address
+        .script_pubkey();
+    let psbt = TxBuilder::new()
+        .add_recipient(&recipient, Arc::new(Amount::from_sat(10_000)))
+        .finish(&wallet)
+        .unwrap();
+
+    // spend an output from imported history the wallet has no record of
+    let mut foreign_tx = psbt.0.lock().unwrap().unsigned_tx.clone();
+    let foreign_outpoint = bdk_wallet::bitcoin::OutPoint {
+        txid: bdk_wallet::bitcoin::hashes::Hash::from_byte_array([4; 32]),
+        vout: 7,
+    };
+    foreign_tx.input[0].previous_output = foreign_outpoint;
+    let output_total: u64 = foreign_tx
+        .output
+        .iter()
+        .map(|txout| txout.value.to_sat())
+        .sum();
+    let foreign_tx: Transaction = foreign_tx.into();
+    assert!(matches!(
+        wallet.calculate_fee(&foreign_tx),
+        Err(CalculateFeeError::MissingTxOut { .. })
+    ));
+
+    wallet.insert_txout(
+        crate::bitcoin::OutPoint {
+            txid: Arc::new(Txid(foreign_outpoint.txid)),
+            vout: foreign_outpoint.vout,
+        },
+        crate::bitcoin::TxOut {
+            value: Arc::new(Amount::from_sat(output_total + 1_234)),
+            script_pubkey: recipient.clone(),
+        },
+    );
+    assert_eq!(wallet.calculate_fee(&foreign_tx).unwrap().to_sat(), 1_234);
+}
+
+#[test]
+fn test_canonical_tx_confirmation_info() {
+    let wallet = TestWallet::funded(&[10_000]).build();
+    let history = wallet.transactions();
+    assert_eq!(history.len(), 1);
+    assert!(history[0].confirmation_info.is_none());
+
+    let tx = history[0].transaction.as_ref().into();
+    mine_block(&wallet, vec![tx]);
+    let history = wallet.transactions();
+    assert_eq!(
+        history[0].confirmation_info,
+        Some(ConfirmationInfo {
+            is_confirmed: true,
+            confirmation_height: 1,
+            confirmation_time: 1,
+            is_transitive: false,
+        })
+    );
+}
+
+#[test]
+fn test_persist_and_load_in_memory() {
+    let persister = Arc::new(Persister::new_in_memory().unwrap());
+    let wallet = Wallet::new(
+        external_descriptor(),
+        internal_descriptor(),
+        Network::Signet,
+        persister.clone(),
+        25,
+    )
+    .unwrap();
+    wallet.reveal_addresses_to(KeychainKind::External, 4);
+    assert!(wallet.persist(persister.clone()).unwrap());
+    drop(wallet);
+
+    let loaded = Wallet::load(external_descriptor(), internal_descriptor(), persister, 25).unwrap();
+    assert_eq!(loaded.network(), Network::Signet);
+    assert_eq!(loaded.derivation_index(KeychainKind::External), Some(4));
+    assert_eq!(
+        loaded
+            .peek_address(KeychainKind::External, 0)
+            .address
+            .to_string(),
+        EXPECTED_FIRST_ADDRESS
+    );
+}
+
+#[test]
+fn test_get_confirmation_block_time() {
+    let wallet = TestWallet::funded(&[10_000]).build();
+    let tx = wallet.transactions()[0].transaction.clone();
+    let txid = tx.compute_txid();
+    assert!(wallet.get_confirmation_block_time(txid.clone()).is_none());
+
+    mine_block(&wallet, vec![tx.as_ref().into()]);
+    let confirmation = wallet.get_confirmation_block_time(txid).unwrap();
+    assert_eq!(confirmation.block_id.height, 1);
+    assert_eq!(
+        confirmation.block_id.hash.0,
+        wallet.get_wallet().latest_checkpoint().hash()
+    );
+    assert_eq!(confirmation.confirmation_time, 1);
+
+    let unknown = Arc::new(Txid(bdk_wallet::bitcoin::hashes::Hash::all_zeros()));
+    assert!(wallet.get_confirmation_block_time(unknown).is_none());
+}
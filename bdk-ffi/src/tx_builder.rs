@@ -5,12 +5,25 @@ use crate::wallet::Wallet;
 
 use bdk_wallet::bitcoin::absolute::LockTime as BdkLockTime;
 use bdk_wallet::bitcoin::amount::Amount as BdkAmount;
+use bdk_wallet::bitcoin::hashes::Hash;
 use bdk_wallet::bitcoin::psbt::Input as BdkInput;
 use bdk_wallet::bitcoin::psbt::PsbtSighashType as BdkPsbtSighashType;
 use bdk_wallet::bitcoin::script::PushBytesBuf;
+use bdk_wallet::bitcoin::FeeRate as BdkFeeRate;
 use bdk_wallet::bitcoin::Psbt as BdkPsbt;
 use bdk_wallet::bitcoin::ScriptBuf as BdkScriptBuf;
-use bdk_wallet::bitcoin::{OutPoint as BdkOutPoint, Sequence, Weight as BdkWeight};
+use bdk_wallet::bitcoin::Transaction as BdkTransaction;
+use bdk_wallet::bitcoin::{
+    OutPoint as BdkOutPoint, Sequence, TxIn as BdkTxIn, TxOut as BdkTxOut, Weight as BdkWeight,
+};
+use bdk_wallet::coin_selection::{
+    CoinSelectionAlgorithm as BdkCoinSelectionAlgorithm,
+    LargestFirstCoinSelection as BdkLargestFirstCoinSelection,
+    OldestFirstCoinSelection as BdkOldestFirstCoinSelection,
+    SingleRandomDraw as BdkSingleRandomDraw,
+};
+use bdk_wallet::error::CreateTxError as BdkCreateTxError;
+use bdk_wallet::TxBuilder as BdkTxBuilder;
 use bdk_wallet::TxOrdering as BdkTxOrdering;
 
 use std::collections::BTreeMap;
@@ -21,6 +34,12 @@ use std::sync::Arc;
 
 type ChangeSpendPolicy = bdk_wallet::ChangeSpendPolicy;
 
+/// The largest `OP_RETURN` payload relayed under the default standardness policy.
+const MAX_OP_RETURN_DATA_LEN: usize = 80;
+
+/// The highest transaction version with defined semantics (version 3 is TRUC, BIP 431).
+const MAX_TX_VERSION: i32 = 3;
+
 /// A `TxBuilder` is created by calling `build_tx` on a wallet. After assigning it, you set options on it until finally
 /// calling `finish` to consume the builder and generate the transaction.
 #[derive(Clone, uniffi::Object)]
@@ -45,6 +64,7 @@ pub struct TxBuilder {
     version: Option<i32>,
     sighash: Option<BdkPsbtSighashType>,
     ordering: TxOrdering,
+    coin_selection: CoinSelectionAlgorithm,
     exclude_unconfirmed: bool,
     exclude_below_confirmations: Option<u32>,
     only_witness_utxo: bool,
@@ -77,6 +97,7 @@ impl TxBuilder {
             version: None,
             sighash: None,
             ordering: TxOrdering::Shuffle,
+            coin_selection: CoinSelectionAlgorithm::BranchAndBound,
             exclude_unconfirmed: false,
             exclude_below_confirmations: None,
             only_witness_utxo: false,
@@ -135,6 +156,20 @@ impl TxBuilder {
         })
     }
 
+    /// Add a list of recipients after the ones already added, in order.
+    pub fn add_recipients(&self, recipients: Vec<ScriptAmount>) -> Arc<Self> {
+        let mut all_recipients: Vec<(BdkScriptBuf, BdkAmount)> = self.recipients.clone();
+        all_recipients.extend(
+            recipients
+                .iter()
+                .map(|script_amount| (script_amount.script.0.clone(), script_amount.amount.0)),
+        );
+        Arc::new(TxBuilder {
+            recipients: all_recipients,
+            ..self.clone()
+        })
+    }
+
     /// Replace the recipients already added with a new list of recipients.
     pub fn set_recipients(&self, recipients: Vec<ScriptAmount>) -> Arc<Self> {
         let recipients = recipients
@@ -249,6 +284,8 @@ impl TxBuilder {
     /// Only spend utxos added by `TxBuilder::add_utxo`.
     ///
     /// The wallet will not add additional utxos to the transaction even if they are needed to make the transaction valid.
+    /// If the selected utxos do not cover the recipients and fee, `finish` returns
+    /// `CreateTxError::InsufficientFunds`.
     pub fn manually_selected_only(&self) -> Arc<Self> {
         Arc::new(TxBuilder {
             manually_selected_only: true,
@@ -264,6 +301,9 @@ impl TxBuilder {
     ///
     /// Note that this is really a minimum feerate – it’s possible to overshoot it slightly since adding a change output
     /// to drain the remaining excess might not be viable.
+    ///
+    /// This option is mutually exclusive with `TxBuilder::fee_absolute`; setting both makes `finish` return
+    /// `CreateTxError::FeePolicyConflict`.
     pub fn fee_rate(&self, fee_rate: &FeeRate) -> Arc<Self> {
         Arc::new(TxBuilder {
             fee_rate: Some(fee_rate.clone()),
@@ -271,9 +311,9 @@ impl TxBuilder {
         })
     }
 
-    /// Set an absolute fee The `fee_absolute` method refers to the absolute transaction fee in `Amount`. If anyone sets
-    /// both the `fee_absolute` method and the `fee_rate` method, the `FeePolicy` enum will be set by whichever method was
-    /// called last, as the `FeeRate` and `FeeAmount` are mutually exclusive.
+    /// Set an absolute fee. The `fee_absolute` method refers to the absolute transaction fee in `Amount`. The fee rate and
+    /// the absolute fee are mutually exclusive, so setting both this and `TxBuilder::fee_rate` makes `finish` return
+    /// `CreateTxError::FeePolicyConflict`.
     ///
     /// Note that this is really a minimum absolute fee – it’s possible to overshoot it slightly since adding a change output to drain the remaining excess might not be viable.
     pub fn fee_absolute(&self, fee_amount: Arc<Amount>) -> Arc<Self> {
@@ -311,6 +351,10 @@ impl TxBuilder {
 
     /// Set an exact `nSequence` value.
     ///
+    /// The value is applied to every input of the transaction, including foreign UTXOs added without an explicit
+    /// sequence. Any value below `0xFFFFFFFE` signals replaceability (BIP 125), so a CSV-compatible sequence also opts
+    /// the transaction into RBF, while `0xFFFFFFFE` and `0xFFFFFFFF` disable it.
+    ///
     /// This can cause conflicts if the wallet’s descriptors contain an "older" (`OP_CSV`) operator and the given
     /// `nsequence` is lower than the CSV value.
     pub fn set_exact_sequence(&self, nsequence: u32) -> Arc<Self> {
@@ -321,6 +365,9 @@ impl TxBuilder {
     }
 
     /// Add data as an output using `OP_RETURN`.
+    ///
+    /// The data must be at most 80 bytes, otherwise `finish` will return a
+    /// `CreateTxError::OpReturnDataTooLarge` error.
     pub fn add_data(&self, data: Vec<u8>) -> Arc<Self> {
         Arc::new(TxBuilder {
             data,
@@ -349,6 +396,9 @@ impl TxBuilder {
     /// Use a specific nLockTime while creating the transaction.
     ///
     /// This can cause conflicts if the wallet’s descriptors contain an "after" (`OP_CLTV`) operator.
+    ///
+    /// A `LockTime::Blocks` height of 500,000,000 or more would be interpreted as a timestamp and is
+    /// rejected by `finish` with `CreateTxError::LockTimeConversionError`.
     pub fn nlocktime(&self, locktime: LockTime) -> Arc<Self> {
         Arc::new(TxBuilder {
             locktime: Some(locktime),
@@ -369,7 +419,8 @@ impl TxBuilder {
     /// Build a transaction with a specific version.
     ///
     /// The version should always be greater than 0 and greater than 1 if the wallet's descriptors contain an "older"
-    /// (`OP_CSV`) operator.
+    /// (`OP_CSV`) operator. Versions above 3 (TRUC) or below 1 are rejected by `finish` with
+    /// `CreateTxError::UnsupportedVersion`.
     pub fn version(&self, version: i32) -> Arc<Self> {
         Arc::new(TxBuilder {
             version: Some(version),
@@ -401,6 +452,16 @@ impl TxBuilder {
         })
     }
 
+    /// Choose the coin selection algorithm used to pick the wallet's UTXOs.
+    ///
+    /// Defaults to `CoinSelectionAlgorithm::BranchAndBound`.
+    pub fn coin_selection(&self, coin_selection: CoinSelectionAlgorithm) -> Arc<Self> {
+        Arc::new(TxBuilder {
+            coin_selection,
+            ..self.clone()
+        })
+    }
+
     /// Only Fill-in the [`psbt::Input::witness_utxo`](bitcoin::psbt::Input::witness_utxo) field
     /// when spending from SegWit descriptors.
     ///
@@ -557,7 +618,30 @@ impl TxBuilder {
     pub fn finish(&self, wallet: &Arc<Wallet>) -> Result<Arc<Psbt>, CreateTxError> {
         // TODO: I had to change the wallet here to be mutable. Why is that now required with the 1.0 API?
         let mut wallet = wallet.get_wallet();
-        let mut tx_builder = wallet.build_tx();
+        let tx_builder = wallet.build_tx();
+        let psbt = match self.coin_selection {
+            // The wallet builds transactions with branch and bound by default.
+            CoinSelectionAlgorithm::BranchAndBound => self.finish_with(tx_builder),
+            CoinSelectionAlgorithm::LargestFirst => {
+                self.finish_with(tx_builder.coin_selection(BdkLargestFirstCoinSelection))
+            }
+            CoinSelectionAlgorithm::OldestFirst => {
+                self.finish_with(tx_builder.coin_selection(BdkOldestFirstCoinSelection))
+            }
+            CoinSelectionAlgorithm::SingleRandomDraw => {
+                self.finish_with(tx_builder.coin_selection(BdkSingleRandomDraw))
+            }
+        }?;
+
+        Ok(Arc::new(psbt.into()))
+    }
+}
+
+impl TxBuilder {
+    fn finish_with<Cs: BdkCoinSelectionAlgorithm>(
+        &self,
+        mut tx_builder: BdkTxBuilder<'_, Cs>,
+    ) -> Result<BdkPsbt, CreateTxError> {
         if self.add_global_xpubs {
             tx_builder.add_global_xpubs();
         }
@@ -582,11 +666,15 @@ impl TxBuilder {
         if self.manually_selected_only {
             tx_builder.manually_selected_only();
         }
-        if let Some(fee_rate) = &self.fee_rate {
-            tx_builder.fee_rate(fee_rate.0);
-        }
-        if let Some(fee_amount) = &self.fee_absolute {
-            tx_builder.fee_absolute(fee_amount.0);
+        match (&self.fee_rate, &self.fee_absolute) {
+            (Some(_), Some(_)) => return Err(CreateTxError::FeePolicyConflict),
+            (Some(fee_rate), None) => {
+                tx_builder.fee_rate(fee_rate.0);
+            }
+            (None, Some(fee_amount)) => {
+                tx_builder.fee_absolute(fee_amount.0);
+            }
+            (None, None) => {}
         }
         if self.drain_wallet {
             tx_builder.drain_wallet();
@@ -598,6 +686,11 @@ impl TxBuilder {
             tx_builder.set_exact_sequence(Sequence(sequence));
         }
         if !&self.data.is_empty() {
+            if self.data.len() > MAX_OP_RETURN_DATA_LEN {
+                return Err(CreateTxError::OpReturnDataTooLarge {
+                    length: self.data.len() as u64,
+                });
+            }
             let push_bytes = PushBytesBuf::try_from(self.data.clone())?;
             tx_builder.add_data(&push_bytes);
         }
@@ -612,6 +705,9 @@ impl TxBuilder {
             tx_builder.allow_dust(self.allow_dust);
         }
         if let Some(version) = self.version {
+            if !(1..=MAX_TX_VERSION).contains(&version) {
+                return Err(CreateTxError::UnsupportedVersion { version });
+            }
             tx_builder.version(version);
         }
         if let Some(sighash) = self.sighash {
@@ -642,9 +738,16 @@ impl TxBuilder {
                     .map_err(AddForeignUtxoError::from)?,
             };
         }
-        let psbt = tx_builder.finish().map_err(CreateTxError::from)?;
-
-        Ok(Arc::new(psbt.into()))
+        tx_builder.finish().map_err(|error| match error {
+            // only the manually selected utxos can be spent, so they do not cover the target
+            BdkCreateTxError::CoinSelection(e) if self.manually_selected_only => {
+                CreateTxError::InsufficientFunds {
+                    needed: e.needed.to_sat(),
+                    available: e.available.to_sat(),
+                }
+            }
+            error => CreateTxError::from(error),
+        })
     }
 }
 
@@ -661,6 +764,7 @@ pub struct BumpFeeTxBuilder {
     version: Option<i32>,
     sighash: Option<BdkPsbtSighashType>,
     ordering: TxOrdering,
+    allow_shrinking: Option<Arc<Script>>,
 }
 
 #[uniffi::export]
@@ -677,9 +781,26 @@ impl BumpFeeTxBuilder {
             version: None,
             sighash: None,
             ordering: TxOrdering::Shuffle,
+            allow_shrinking: None,
         }
     }
 
+    /// Let the output paying to `script` be reduced to pay for the fee increase.
+    ///
+    /// Without this the wallet pays the higher fee out of its change output, or by adding inputs.
+    /// With it the replacement spends the same inputs, every other output keeps its value, including
+    /// any change, and the designated output is reduced by the fee increase. The output may pay to a
+    /// recipient, in which case that recipient pays for the bump. It may shrink below the dust limit
+    /// and be removed, and is not guaranteed to keep its position in the transaction.
+    ///
+    /// `finish` returns `CreateTxError::ShrinkOutputNotFound` if no output pays to `script`.
+    pub fn allow_shrinking(&self, script: Arc<Script>) -> Arc<Self> {
+        Arc::new(BumpFeeTxBuilder {
+            allow_shrinking: Some(script),
+            ..self.clone()
+        })
+    }
+
     /// Set an exact `nSequence` value.
     ///
     /// This can cause conflicts if the wallet’s descriptors contain an "older" (`OP_CSV`) operator and the given
@@ -695,8 +816,9 @@ impl BumpFeeTxBuilder {
     ///
     /// This will be used to:
     ///
-    /// 1. Set the `nLockTime` for preventing fee sniping. Note: This will be ignored if you manually specify a
-    ///    `nlocktime` using `TxBuilder::nlocktime`.
+    /// 1. Unlike `TxBuilder::current_height`, it does not move the `nLockTime` forward to prevent fee sniping: the
+    ///    replacement keeps the `nLockTime` of the transaction being replaced. Use `BumpFeeTxBuilder::nlocktime` to
+    ///    override it.
     ///
     /// 2. Decide whether coinbase outputs are mature or not. If the coinbase outputs are not mature at `current_height`,
     ///    we ignore them in the coin selection. If you want to create a transaction that spends immature coinbase inputs,
@@ -712,6 +834,11 @@ impl BumpFeeTxBuilder {
     /// Use a specific nLockTime while creating the transaction.
     ///
     /// This can cause conflicts if the wallet’s descriptors contain an "after" (`OP_CLTV`) operator.
+    ///
+    /// Without it the replacement keeps the `nLockTime` of the transaction being replaced.
+    ///
+    /// A `LockTime::Blocks` height of 500,000,000 or more would be interpreted as a timestamp and is
+    /// rejected by `finish` with `CreateTxError::LockTimeConversionError`.
     pub fn nlocktime(&self, locktime: LockTime) -> Arc<Self> {
         Arc::new(BumpFeeTxBuilder {
             locktime: Some(locktime),
@@ -732,7 +859,8 @@ impl BumpFeeTxBuilder {
     /// Build a transaction with a specific version.
     ///
     /// The version should always be greater than 0 and greater than 1 if the wallet’s descriptors contain an "older"
-    /// (`OP_CSV`) operator.
+    /// (`OP_CSV`) operator. Versions above 3 (TRUC) or below 1 are rejected by `finish` with
+    /// `CreateTxError::UnsupportedVersion`.
     pub fn version(&self, version: i32) -> Arc<Self> {
         Arc::new(BumpFeeTxBuilder {
             version: Some(version),
@@ -764,34 +892,97 @@ impl BumpFeeTxBuilder {
         })
     }
 
+    /// The lowest fee rate `finish` accepts: the fee rate of the transaction being replaced plus the
+    /// incremental relay fee of 1 sat/vB, as BIP 125 requires a replacement to pay for its own
+    /// relay on top of the fees of the transaction it replaces.
+    ///
+    /// Returns `CreateTxError::TransactionNotFound` if the wallet does not know the transaction, and
+    /// `CreateTxError::CalculateFee` if the outputs it spends are missing from the wallet.
+    pub fn minimum_bump_fee_rate(
+        &self,
+        wallet: &Arc<Wallet>,
+    ) -> Result<Arc<FeeRate>, CreateTxError> {
+        let wallet = wallet.get_wallet();
+        let tx =
+            wallet
+                .tx_graph()
+                .get_tx(self.txid.0)
+                .ok_or(CreateTxError::TransactionNotFound {
+                    txid: self.txid.0.to_string(),
+                })?;
+        required_bump_fee_rate(&wallet, &tx).map(|fee_rate| Arc::new(FeeRate(fee_rate)))
+    }
+
     /// Finish building the transaction.
     ///
     /// Uses the thread-local random number generator (rng).
     ///
     /// Returns a new `Psbt` per BIP174.
     ///
+    /// Returns `CreateTxError::FeeRateTooLow` if the fee rate is below `minimum_bump_fee_rate`,
+    /// `CreateTxError::TransactionNotFound` if the wallet does not know the transaction, and
+    /// `CreateTxError::CalculateFee` if the fee of the transaction being replaced is unknown.
+    ///
     /// WARNING: To avoid change address reuse you must persist the changes resulting from one or more calls to this
     /// method before closing the wallet. See `Wallet::reveal_next_address`.
     pub fn finish(&self, wallet: &Arc<Wallet>) -> Result<Arc<Psbt>, CreateTxError> {
         let mut wallet = wallet.get_wallet();
+        // an unknown transaction is reported by `build_fee_bump` below
+        let original_tx = wallet.tx_graph().get_tx(self.txid.0);
+        let mut shrink = None;
+        if let Some(script) = &self.allow_shrinking {
+            if let Some(tx) = &original_tx {
+                let shrink_index = tx
+                    .output
+                    .iter()
+                    .position(|txout| txout.script_pubkey == script.0)
+                    .ok_or(CreateTxError::ShrinkOutputNotFound)?;
+                // the other outputs, change included, are kept as they are
+                let recipients: Vec<(BdkScriptBuf, BdkAmount)> = tx
+                    .output
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| *index != shrink_index)
+                    .map(|(_, txout)| (txout.script_pubkey.clone(), txout.value))
+                    .collect();
+                shrink = Some((script.0.clone(), recipients));
+            }
+        }
         let mut tx_builder = wallet
             .build_fee_bump(self.txid.0)
             .map_err(CreateTxError::from)?;
         tx_builder.fee_rate(self.fee_rate.0);
+        if let Some((script, recipients)) = shrink {
+            // only the inputs of the original transaction are spent, so the fee increase comes out
+            // of the output draining what is left
+            tx_builder.set_recipients(recipients);
+            tx_builder.drain_to(script);
+            tx_builder.manually_selected_only();
+        }
         if let Some(sequence) = self.sequence {
             tx_builder.set_exact_sequence(Sequence(sequence));
         }
         if let Some(height) = self.current_height {
             tx_builder.current_height(height);
         }
-        if let Some(locktime) = &self.locktime {
-            let bdk_locktime: BdkLockTime = locktime.try_into()?;
-            tx_builder.nlocktime(bdk_locktime);
+        match (&self.locktime, &original_tx) {
+            (Some(locktime), _) => {
+                let bdk_locktime: BdkLockTime = locktime.try_into()?;
+                tx_builder.nlocktime(bdk_locktime);
+            }
+            // keep the timelock of the transaction being replaced
+            (None, Some(tx)) => {
+                tx_builder.nlocktime(tx.lock_time);
+            }
+            (None, None) => {}
         }
         if self.allow_dust {
             tx_builder.allow_dust(self.allow_dust);
         }
         if let Some(version) = self.version {
+            if !(1..=MAX_TX_VERSION).contains(&version) {
+                return Err(CreateTxError::UnsupportedVersion { version });
+            }
             tx_builder.version(version);
         }
         if let Some(sighash) = self.sighash {
@@ -805,6 +996,20 @@ impl BumpFeeTxBuilder {
     }
 }
 
+fn required_bump_fee_rate(
+    wallet: &bdk_wallet::Wallet,
+    tx: &BdkTransaction,
+) -> Result<BdkFeeRate, CreateTxError> {
+    let fee_rate = wallet
+        .calculate_fee_rate(tx)
+        .map_err(|error| CreateTxError::CalculateFee {
+            error_message: error.to_string(),
+        })?;
+    Ok(BdkFeeRate::from_sat_per_kwu(
+        fee_rate.to_sat_per_kwu() + BdkFeeRate::BROADCAST_MIN.to_sat_per_kwu(),
+    ))
+}
+
 /// Policy regarding the use of change outputs when creating a transaction.
 #[uniffi::remote(Enum)]
 pub enum ChangeSpendPolicy {
@@ -831,6 +1036,24 @@ pub enum TxOrdering {
     /// guarantees about algorithmically selected UTXOs. However, by design they will always be
     /// placed after the manually selected ones.
     Untouched,
+    /// BIP-69 lexicographic ordering: inputs by previous txid and output index, outputs by amount
+    /// and then `scriptPubKey`.
+    Bip69Lexicographic,
+}
+
+/// The algorithm used to select the wallet's UTXOs to fund a transaction.
+#[derive(Clone, Copy, Debug, Default, uniffi::Enum)]
+pub enum CoinSelectionAlgorithm {
+    /// Branch and bound coin selection, which tries to avoid creating a change output and
+    /// falls back to single random draw (default).
+    #[default]
+    BranchAndBound,
+    /// Select the largest UTXOs first.
+    LargestFirst,
+    /// Select the oldest UTXOs first.
+    OldestFirst,
+    /// Select UTXOs at random until the target is reached.
+    SingleRandomDraw,
 }
 
 impl From<TxOrdering> for BdkTxOrdering {
@@ -838,6 +1061,23 @@ impl From<TxOrdering> for BdkTxOrdering {
         match value {
             TxOrdering::Shuffle => BdkTxOrdering::Shuffle,
             TxOrdering::Untouched => BdkTxOrdering::Untouched,
+            TxOrdering::Bip69Lexicographic => BdkTxOrdering::Custom {
+                input_sort: Arc::new(|a: &BdkTxIn, b: &BdkTxIn| {
+                    // txids are compared in their displayed, byte-reversed, form
+                    let a_txid = a.previous_output.txid.to_byte_array();
+                    let b_txid = b.previous_output.txid.to_byte_array();
+                    a_txid
+                        .iter()
+                        .rev()
+                        .cmp(b_txid.iter().rev())
+                        .then(a.previous_output.vout.cmp(&b.previous_output.vout))
+                }),
+                output_sort: Arc::new(|a: &BdkTxOut, b: &BdkTxOut| {
+                    a.value
+                        .cmp(&b.value)
+                        .then_with(|| a.script_pubkey.as_bytes().cmp(b.script_pubkey.as_bytes()))
+                }),
+            },
         }
     }
 }
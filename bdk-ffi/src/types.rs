@@ -1,20 +1,24 @@
 use crate::bitcoin::{
-    Address, Amount, BlockHash, DescriptorId, FeeRate, HashableOutPoint, OutPoint, Script,
-    Transaction, TxOut, Txid,
+    Address, Amount, BlockHash, DescriptorId, DescriptorType, FeeRate, HashableOutPoint, OutPoint,
+    Script, Transaction, TxOut, Txid,
 };
 use crate::descriptor::Descriptor;
-use crate::error::{CreateTxError, RequestBuilderError};
+use crate::error::{CannotConnectError, ChangeSetJsonError, CreateTxError, RequestBuilderError};
+use crate::wallet::Wallet;
 
 use bdk_wallet::bitcoin::absolute::LockTime as BdkLockTime;
 use bdk_wallet::chain::spk_client::SyncItem;
 use bdk_wallet::chain::BlockId as BdkBlockId;
+use bdk_wallet::chain::CheckPoint;
 use bdk_wallet::chain::Merge;
 
+use bdk_wallet::bitcoin::Script as BdkScript;
 use bdk_wallet::bitcoin::Transaction as BdkTransaction;
 use bdk_wallet::chain::spk_client::FullScanRequest as BdkFullScanRequest;
 use bdk_wallet::chain::spk_client::FullScanRequestBuilder as BdkFullScanRequestBuilder;
 use bdk_wallet::chain::spk_client::SyncRequest as BdkSyncRequest;
 use bdk_wallet::chain::spk_client::SyncRequestBuilder as BdkSyncRequestBuilder;
+use bdk_wallet::chain::spk_client::SyncResponse as BdkSyncResponse;
 use bdk_wallet::chain::tx_graph::CanonicalTx as BdkCanonicalTx;
 use bdk_wallet::chain::{
     ChainPosition as BdkChainPosition, ConfirmationBlockTime as BdkConfirmationBlockTime,
@@ -36,6 +40,7 @@ use bdk_wallet::WalletEvent as BdkWalletEvent;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryFrom;
 use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 use crate::{impl_from_core_type, impl_into_core_type};
@@ -161,6 +166,38 @@ impl From<BlockId> for BdkBlockId {
     }
 }
 
+/// The confirmation of a transaction, flattened for display in a transaction history.
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct ConfirmationInfo {
+    /// Whether the transaction is confirmed. Always `true` for the record of
+    /// `CanonicalTx::confirmation_info`, which is `None` for unconfirmed transactions.
+    pub is_confirmed: bool,
+    /// The height of the block confirming the transaction.
+    pub confirmation_height: u32,
+    /// The confirmation time of the transaction, as a unix timestamp.
+    pub confirmation_time: u64,
+    /// Whether the block is only known through a confirmed descendant, in which case the
+    /// transaction is confirmed at or below `confirmation_height`.
+    pub is_transitive: bool,
+}
+
+impl ChainPosition {
+    pub(crate) fn confirmation_info(&self) -> Option<ConfirmationInfo> {
+        match self {
+            ChainPosition::Confirmed {
+                confirmation_block_time,
+                transitively,
+            } => Some(ConfirmationInfo {
+                is_confirmed: true,
+                confirmation_height: confirmation_block_time.block_id.height,
+                confirmation_time: confirmation_block_time.confirmation_time,
+                is_transitive: transitively.is_some(),
+            }),
+            ChainPosition::Unconfirmed { .. } => None,
+        }
+    }
+}
+
 /// A transaction that is deemed to be part of the canonical history.
 #[derive(uniffi::Record)]
 pub struct CanonicalTx {
@@ -168,13 +205,17 @@ pub struct CanonicalTx {
     pub transaction: Arc<Transaction>,
     /// How the transaction is observed in the canonical chain (confirmed or unconfirmed).
     pub chain_position: ChainPosition,
+    /// The confirmation details of `chain_position`, or `None` if the transaction is unconfirmed.
+    pub confirmation_info: Option<ConfirmationInfo>,
 }
 
 impl From<BdkCanonicalTx<'_, Arc<BdkTransaction>, BdkConfirmationBlockTime>> for CanonicalTx {
     fn from(tx: BdkCanonicalTx<'_, Arc<BdkTransaction>, BdkConfirmationBlockTime>) -> Self {
+        let chain_position: ChainPosition = tx.chain_position.into();
         CanonicalTx {
             transaction: Arc::new(Transaction::from(tx.tx_node.tx.as_ref().clone())),
-            chain_position: tx.chain_position.into(),
+            confirmation_info: chain_position.confirmation_info(),
+            chain_position,
         }
     }
 }
@@ -188,6 +229,19 @@ pub struct ScriptAmount {
     pub amount: Arc<Amount>,
 }
 
+/// The coins, change and fee a transaction would use, see `Wallet.preview_selection`.
+#[derive(uniffi::Record)]
+pub struct SelectionPreview {
+    /// The wallet outputs the transaction would spend.
+    pub selected: Vec<OutPoint>,
+    /// The total value of the selected outputs.
+    pub total_input: Arc<Amount>,
+    /// The value sent back to the wallet as change, if the transaction has a change output.
+    pub change: Option<Arc<Amount>>,
+    /// The fee the transaction would pay.
+    pub fee: Arc<Amount>,
+}
+
 /// A derived address and the index it was found at.
 #[derive(uniffi::Record)]
 pub struct AddressInfo {
@@ -199,6 +253,15 @@ pub struct AddressInfo {
     pub keychain: KeychainKind,
 }
 
+/// A key of a wallet descriptor, and whether the wallet can sign for it.
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct SignerInfo {
+    /// The fingerprint of the master key the descriptor key derives from.
+    pub fingerprint: String,
+    /// Whether the wallet holds a signer with the secret for this key.
+    pub has_secret: bool,
+}
+
 impl From<BdkAddressInfo> for AddressInfo {
     fn from(address_info: BdkAddressInfo) -> Self {
         AddressInfo {
@@ -284,6 +347,68 @@ pub trait FullScanScriptInspector: Sync + Send {
     fn inspect(&self, keychain: KeychainKind, index: u32, script: Arc<Script>);
 }
 
+/// A script pubkey of a keychain along with its derivation index.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct IndexedScript {
+    pub index: u32,
+    pub script: Arc<Script>,
+}
+
+// Batched callback for the FullScanRequest
+#[uniffi::export(with_foreign)]
+pub trait FullScanBatchScriptInspector: Sync + Send {
+    fn inspect_batch(&self, keychain: KeychainKind, scripts: Vec<IndexedScript>);
+}
+
+// Progress callback for a full scan
+#[uniffi::export(with_foreign)]
+pub trait FullScanProgress: Sync + Send {
+    /// Called as the scan reaches the script at `spk_index` of `keychain`. The length of a full
+    /// scan is only known once it ends, so `total_estimated` is the index the scan stops at if none
+    /// of the remaining scripts have been used. It grows as used scripts are found.
+    fn on_progress(&self, keychain: KeychainKind, spk_index: u32, total_estimated: u32);
+}
+
+/// Buffers inspected scripts so the foreign inspector is called once per batch. A partial batch is
+/// handed over when the keychain changes and when the request is dropped after the scan.
+struct BatchingFullScanInspector {
+    inspector: Arc<dyn FullScanBatchScriptInspector>,
+    batch_size: usize,
+    keychain: Option<KeychainKind>,
+    scripts: Vec<IndexedScript>,
+}
+
+impl BatchingFullScanInspector {
+    fn push(&mut self, keychain: KeychainKind, index: u32, script: &BdkScript) {
+        if self.keychain != Some(keychain) {
+            self.flush();
+            self.keychain = Some(keychain);
+        }
+        self.scripts.push(IndexedScript {
+            index,
+            script: Arc::new(Script(script.to_owned())),
+        });
+        if self.scripts.len() >= self.batch_size {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(keychain) = self.keychain {
+            if !self.scripts.is_empty() {
+                self.inspector
+                    .inspect_batch(keychain, std::mem::take(&mut self.scripts));
+            }
+        }
+    }
+}
+
+impl Drop for BatchingFullScanInspector {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
 // Callback for the SyncRequest
 #[uniffi::export(with_foreign)]
 pub trait SyncScriptInspector: Sync + Send {
@@ -304,8 +429,205 @@ pub struct FullScanRequest(pub(crate) Mutex<Option<BdkFullScanRequest<KeychainKi
 #[derive(uniffi::Object)]
 pub struct SyncRequest(pub(crate) Mutex<Option<BdkSyncRequest<(KeychainKind, u32)>>>);
 
+/// A handle to abort a `full_scan` or `sync` in progress, for instance when the app is
+/// backgrounded or the user switches servers. Pass the same token to the scan and keep a
+/// reference to call `cancel` from another thread.
+#[derive(Default, uniffi::Object)]
+pub struct ScanToken(AtomicBool);
+
+#[uniffi::export]
+impl ScanToken {
+    #[uniffi::constructor]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the scan holding this token to stop. A full scan stops requesting scripts and returns
+    /// once the requests already sent are answered. A sync stops before its next round trip to
+    /// the server. A cancelled token stays cancelled.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether `cancel` has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl ScanToken {
+    /// Sync `request` with `sync` in chunks of at most `chunk_size` items, so that the token is
+    /// checked between round trips to the server. Returns `None` if the token is cancelled before
+    /// the last chunk.
+    ///
+    /// Every chunk is a sync of its own and fetches the chain tip again, so this takes more round
+    /// trips than syncing the whole request at once. If the chain changes between two chunks,
+    /// their chain updates conflict and the `CannotConnectError` is returned.
+    pub(crate) fn sync_in_chunks<E: From<CannotConnectError>>(
+        &self,
+        mut request: BdkSyncRequest<(KeychainKind, u32)>,
+        chunk_size: usize,
+        mut sync: impl FnMut(BdkSyncRequest) -> Result<BdkSyncResponse, E>,
+    ) -> Result<Option<BdkSyncResponse>, E> {
+        let chunk_size = chunk_size.max(1);
+        let mut response = BdkSyncResponse::default();
+        loop {
+            if self.is_cancelled() {
+                return Ok(None);
+            }
+            // items are taken from the request one chunk at a time, so an inspector set on it
+            // still reports them as they are synced
+            let mut chunk = BdkSyncRequest::builder_at(request.start_time());
+            if let Some(chain_tip) = request.chain_tip() {
+                chunk = chunk.chain_tip(chain_tip);
+            }
+            let mut spks = Vec::new();
+            let mut expected_txids = Vec::new();
+            let mut txids = Vec::new();
+            let mut outpoints = Vec::new();
+            for _ in 0..chunk_size {
+                if let Some(spk) = request.next_spk_with_expected_txids() {
+                    let script = spk.spk;
+                    expected_txids.extend(
+                        spk.expected_txids
+                            .into_iter()
+                            .map(|txid| (script.clone(), txid)),
+                    );
+                    spks.push(script);
+                } else if let Some(txid) = request.next_txid() {
+                    txids.push(txid);
+                } else if let Some(outpoint) = request.next_outpoint() {
+                    outpoints.push(outpoint);
+                } else {
+                    break;
+                }
+            }
+            let chunk = chunk
+                .spks(spks)
+                .expected_spk_txids(expected_txids)
+                .txids(txids)
+                .outpoints(outpoints)
+                .build();
+
+            let chunk_response = sync(chunk)?;
+            response.tx_update.extend(chunk_response.tx_update);
+            response.chain_update = match (response.chain_update, chunk_response.chain_update) {
+                (Some(chain), Some(chunk_chain)) => Some(merge_chains(chain, chunk_chain)?),
+                (chain, chunk_chain) => chunk_chain.or(chain),
+            };
+            if request.progress().remaining() == 0 {
+                return Ok(Some(response));
+            }
+        }
+    }
+}
+
+/// Wrap `request` so that `progress` is told about every script the scan pulls from it, and no
+/// more scripts are handed out once `token` is cancelled. The scripts are still drawn from the
+/// original request, so an inspector set on it keeps being called as well.
+///
+/// The returned flag is set if the scan asked for a script after the token was cancelled, meaning
+/// its result is incomplete.
+pub(crate) fn wrap_full_scan_request(
+    request: BdkFullScanRequest<KeychainKind>,
+    stop_gap: u64,
+    progress: Option<Arc<dyn FullScanProgress>>,
+    token: Option<Arc<ScanToken>>,
+) -> (BdkFullScanRequest<KeychainKind>, Arc<AtomicBool>) {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    if progress.is_none() && token.is_none() {
+        return (request, interrupted);
+    }
+    let keychains = request.keychains();
+    let mut builder = BdkFullScanRequest::builder_at(request.start_time());
+    if let Some(chain_tip) = request.chain_tip() {
+        builder = builder.chain_tip(chain_tip);
+    }
+    let request = Arc::new(Mutex::new(request));
+    for keychain in keychains {
+        builder = builder.spks_for_keychain(
+            keychain,
+            KeychainSpks {
+                request: request.clone(),
+                keychain,
+                token: token.clone(),
+                interrupted: interrupted.clone(),
+            },
+        );
+    }
+    if let Some(progress) = progress {
+        let stop_gap = u32::try_from(stop_gap).unwrap_or(u32::MAX);
+        builder = builder.inspect(move |keychain, spk_index, _| {
+            progress.on_progress(keychain, spk_index, spk_index.saturating_add(stop_gap))
+        });
+    }
+    (builder.build(), interrupted)
+}
+
+/// Pulls the scripts of one keychain out of a shared full scan request, until the token, if any,
+/// is cancelled.
+struct KeychainSpks {
+    request: Arc<Mutex<BdkFullScanRequest<KeychainKind>>>,
+    keychain: KeychainKind,
+    token: Option<Arc<ScanToken>>,
+    interrupted: Arc<AtomicBool>,
+}
+
+impl Iterator for KeychainSpks {
+    type Item = (u32, bdk_wallet::bitcoin::ScriptBuf);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self
+            .token
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+        {
+            self.interrupted.store(true, Ordering::SeqCst);
+            return None;
+        }
+        self.request.lock().unwrap().iter_spks(self.keychain).next()
+    }
+}
+
 #[uniffi::export]
 impl SyncRequestBuilder {
+    /// Start a sync request that only covers the `count` most recently revealed script pubkeys
+    /// of `keychain`. This is a cheap way to refresh the balance of a wallet with a long address
+    /// history, at the cost of missing activity on older addresses.
+    #[uniffi::constructor]
+    pub fn revealed_spks_up_to(
+        wallet: Arc<Wallet>,
+        keychain: KeychainKind,
+        count: u32,
+    ) -> Arc<Self> {
+        let wallet = wallet.get_wallet();
+        let spks = wallet
+            .spk_index()
+            .revealed_keychain_spks(keychain)
+            .rev()
+            .take(count as usize)
+            .map(|(index, spk)| ((keychain, index), spk))
+            .collect::<Vec<_>>();
+        // the scripts are in descending order of derivation index
+        let expected_spk_txids = match (spks.last(), spks.first()) {
+            (Some((lowest, _)), Some((highest, _))) => wallet
+                .tx_graph()
+                .list_expected_spk_txids(
+                    wallet.local_chain(),
+                    wallet.latest_checkpoint().block_id(),
+                    wallet.spk_index(),
+                    *lowest..=*highest,
+                )
+                .collect(),
+            _ => Vec::new(),
+        };
+        let builder = BdkSyncRequest::builder()
+            .chain_tip(wallet.latest_checkpoint())
+            .spks_with_indexes(spks)
+            .expected_spk_txids(expected_spk_txids);
+        Arc::new(SyncRequestBuilder(Mutex::new(Some(builder))))
+    }
+
     pub fn inspect_spks(
         &self,
         inspector: Arc<dyn SyncScriptInspector>,
@@ -328,6 +650,49 @@ impl SyncRequestBuilder {
         )))))
     }
 
+    /// Mark `txids` as unconfirmed transactions the wallet expects to still find in the mempool.
+    ///
+    /// Each transaction is paired with the wallet scripts it pays to or spends from. When the
+    /// chain source no longer reports one of these transactions in the history of those scripts,
+    /// the resulting update evicts it from the wallet. The scripts must also be part of the
+    /// request, which is the case for requests started from `Wallet::start_sync_with_revealed_spks`.
+    pub fn expected_unconfirmed_txids(
+        &self,
+        wallet: Arc<Wallet>,
+        txids: Vec<Arc<Txid>>,
+    ) -> Result<Arc<Self>, RequestBuilderError> {
+        let guard = self
+            .0
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or(RequestBuilderError::RequestAlreadyConsumed)?;
+        let wallet = wallet.get_wallet();
+        let graph = wallet.tx_graph();
+        let mut expected = Vec::new();
+        for txid in txids {
+            let tx = match graph.get_tx(txid.0) {
+                Some(tx) => tx,
+                None => continue,
+            };
+            let spent = tx
+                .input
+                .iter()
+                .filter_map(|input| graph.get_txout(input.previous_output))
+                .map(|txout| txout.script_pubkey.clone());
+            let received = tx.output.iter().map(|txout| txout.script_pubkey.clone());
+            expected.extend(
+                spent
+                    .chain(received)
+                    .filter(|spk| wallet.is_mine(spk.clone()))
+                    .map(|spk| (spk, txid.0)),
+            );
+        }
+        Ok(Arc::new(SyncRequestBuilder(Mutex::new(Some(
+            guard.expected_spk_txids(expected),
+        )))))
+    }
+
     pub fn build(&self) -> Result<Arc<SyncRequest>, RequestBuilderError> {
         let guard = self
             .0
@@ -359,6 +724,32 @@ impl FullScanRequestBuilder {
         )))))
     }
 
+    /// Like `inspect_spks_for_all_keychains`, but calls the inspector once per `batch_size`
+    /// scripts to reduce the number of calls across the language boundary on large scans.
+    pub fn inspect_spks_for_all_keychains_batched(
+        &self,
+        inspector: Arc<dyn FullScanBatchScriptInspector>,
+        batch_size: u32,
+    ) -> Result<Arc<Self>, RequestBuilderError> {
+        let guard = self
+            .0
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or(RequestBuilderError::RequestAlreadyConsumed)?;
+        let mut batcher = BatchingFullScanInspector {
+            inspector,
+            batch_size: batch_size.max(1) as usize,
+            keychain: None,
+            scripts: Vec::new(),
+        };
+        let full_scan_request_builder =
+            guard.inspect(move |keychain, index, script| batcher.push(keychain, index, script));
+        Ok(Arc::new(FullScanRequestBuilder(Mutex::new(Some(
+            full_scan_request_builder,
+        )))))
+    }
+
     pub fn build(&self) -> Result<Arc<FullScanRequest>, RequestBuilderError> {
         let guard = self
             .0
@@ -374,6 +765,57 @@ impl FullScanRequestBuilder {
 #[derive(uniffi::Object)]
 pub struct Update(pub(crate) BdkUpdate);
 
+#[uniffi::export]
+impl Update {
+    /// Combine this update with another, for example a full scan with a later mempool sync, so
+    /// both can be applied at once.
+    ///
+    /// Transaction data from both updates is kept and each keychain uses the higher last active
+    /// index. If both updates carry chain data, the blocks of the lower chain are added to the
+    /// higher one, so blocks anchoring transactions of either update are kept. Returns
+    /// `CannotConnectError` if the two chains hold different blocks at the same height.
+    pub fn merge(&self, other: Arc<Update>) -> Result<Arc<Update>, CannotConnectError> {
+        let mut update = self.0.clone();
+        let other = other.0.clone();
+        for (keychain, index) in other.last_active_indices {
+            update
+                .last_active_indices
+                .entry(keychain)
+                .and_modify(|last_active| *last_active = (*last_active).max(index))
+                .or_insert(index);
+        }
+        update.tx_update.extend(other.tx_update);
+        update.chain = match (update.chain, other.chain) {
+            (Some(chain), Some(other_chain)) => Some(merge_chains(chain, other_chain)?),
+            (chain, other_chain) => chain.or(other_chain),
+        };
+        Ok(Arc::new(Update(update)))
+    }
+}
+
+/// Insert every block of the lower of two chains into the higher one. Fails on the first height
+/// where the chains disagree.
+fn merge_chains(chain: CheckPoint, other: CheckPoint) -> Result<CheckPoint, CannotConnectError> {
+    let (mut higher, lower) = if other.height() >= chain.height() {
+        (other, chain)
+    } else {
+        (chain, other)
+    };
+    for checkpoint in lower.iter() {
+        let block_id = checkpoint.block_id();
+        match higher.get(block_id.height) {
+            Some(existing) if existing.hash() != block_id.hash => {
+                return Err(CannotConnectError::Include {
+                    height: block_id.height,
+                });
+            }
+            Some(_) => {}
+            None => higher = higher.insert(block_id),
+        }
+    }
+    Ok(higher)
+}
+
 /// The total value sent and received.
 #[derive(uniffi::Record)]
 pub struct SentAndReceivedValues {
@@ -1323,6 +1765,16 @@ impl ChangeSet {
         changeset.into()
     }
 
+    /// Deserialize a `ChangeSet` previously serialized with `ChangeSet::to_json`.
+    #[uniffi::constructor]
+    pub fn from_json(json: String) -> Result<Self, ChangeSetJsonError> {
+        bdk_wallet::serde_json::from_str::<bdk_wallet::ChangeSet>(&json)
+            .map(ChangeSet::from)
+            .map_err(|e| ChangeSetJsonError::Deserialize {
+                error_message: e.to_string(),
+            })
+    }
+
     /// Build a `ChangeSet` by merging together two `ChangeSet`.
     #[uniffi::constructor]
     pub fn from_merge(left: Arc<ChangeSet>, right: Arc<ChangeSet>) -> Self {
@@ -1366,6 +1818,16 @@ impl ChangeSet {
     pub fn locked_outpoints_changeset(&self) -> HashMap<Arc<HashableOutPoint>, bool> {
         self.locked_outpoints.0.clone()
     }
+
+    /// Serialize the `ChangeSet` to JSON, for callers who store wallet changes themselves.
+    ///
+    /// Descriptors are serialized without their secret keys.
+    pub fn to_json(&self) -> Result<String, ChangeSetJsonError> {
+        let changeset: bdk_wallet::ChangeSet = self.clone().into();
+        bdk_wallet::serde_json::to_string(&changeset).map_err(|e| ChangeSetJsonError::Serialize {
+            error_message: e.to_string(),
+        })
+    }
 }
 
 impl From<ChangeSet> for bdk_wallet::ChangeSet {
@@ -1481,3 +1943,72 @@ impl From<WildcardType> for Wildcard {
         }
     }
 }
+
+/// Script types defined by BIP-48 for multisig account derivation paths.
+#[derive(Debug, Clone, Copy, uniffi::Enum, PartialEq)]
+pub enum Bip48ScriptType {
+    /// P2SH nested P2WSH, derived at script type `1'`.
+    P2shP2wsh,
+    /// Native P2WSH, derived at script type `2'`.
+    P2wsh,
+}
+
+/// Script types a multisig descriptor can be wrapped in.
+#[derive(Debug, Clone, Copy, uniffi::Enum, PartialEq)]
+pub enum MultisigScriptType {
+    /// Native P2WSH, e.g. `wsh(sortedmulti(...))`.
+    Wsh,
+    /// P2SH nested P2WSH, e.g. `sh(wsh(sortedmulti(...)))`.
+    ShWsh,
+}
+
+/// The output script type of a descriptor, independent of the script it commits to.
+#[derive(Debug, Clone, Copy, uniffi::Enum, PartialEq)]
+pub enum DescriptorScriptType {
+    /// Pay to public key hash, also known as legacy.
+    P2pkh,
+    /// P2SH nested pay to witness public key hash, also known as nested segwit.
+    P2shP2wpkh,
+    /// Pay to witness public key hash, also known as native segwit.
+    P2wpkh,
+    /// Pay to taproot.
+    P2tr,
+    /// Pay to witness script hash.
+    P2wsh,
+    /// P2SH nested pay to witness script hash.
+    P2shP2wsh,
+    /// Pay to script hash.
+    P2sh,
+    /// A bare script, such as pay to public key.
+    Bare,
+}
+
+/// A summary of a descriptor, see `Descriptor::info`.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct DescriptorInfo {
+    /// Whether the descriptor has wildcards and derives a range of scripts.
+    pub is_ranged: bool,
+    /// Whether the descriptor has multiple derivation paths, like `<0;1>`.
+    pub is_multipath: bool,
+    /// The type of output script the descriptor produces.
+    pub script_type: DescriptorScriptType,
+    /// Whether the descriptor was given with secret keys.
+    pub has_secret: bool,
+}
+
+impl From<DescriptorType> for DescriptorScriptType {
+    fn from(value: DescriptorType) -> Self {
+        match value {
+            DescriptorType::Bare => DescriptorScriptType::Bare,
+            DescriptorType::Sh | DescriptorType::ShSortedMulti => DescriptorScriptType::P2sh,
+            DescriptorType::Pkh => DescriptorScriptType::P2pkh,
+            DescriptorType::Wpkh => DescriptorScriptType::P2wpkh,
+            DescriptorType::Wsh | DescriptorType::WshSortedMulti => DescriptorScriptType::P2wsh,
+            DescriptorType::ShWsh | DescriptorType::ShWshSortedMulti => {
+                DescriptorScriptType::P2shP2wsh
+            }
+            DescriptorType::ShWpkh => DescriptorScriptType::P2shP2wpkh,
+            DescriptorType::Tr => DescriptorScriptType::P2tr,
+        }
+    }
+}
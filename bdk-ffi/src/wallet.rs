@@ -1,19 +1,37 @@
-use crate::bitcoin::{Amount, FeeRate, OutPoint, Psbt, Script, Transaction, TxOut, Txid};
+use crate::bitcoin::{
+    Address, Amount, Block, FeeRate, OutPoint, Psbt, Script, Transaction, TxOut, Txid,
+};
 use crate::descriptor::Descriptor;
 use crate::error::{
-    CalculateFeeError, CannotConnectError, CreateWithPersistError, DescriptorError,
-    LoadWithPersistError, PersistenceError, SignerError, TxidParseError,
+    CalculateFeeError, CannotConnectError, CreateTxError, CreateWithPersistError, DescriptorError,
+    LoadWithPersistError, PersistenceError, SignerError,
 };
-use crate::store::{PersistenceType, Persister};
+use crate::store::{ChangeSetSnapshot, PersistenceType, Persister};
 use crate::types::{
-    AddressInfo, Balance, BlockId, CanonicalTx, ChangeSet, EvictedTx, FullScanRequestBuilder,
-    KeychainAndIndex, KeychainKind, LocalOutput, Policy, SentAndReceivedValues, SignOptions,
-    SyncRequestBuilder, UnconfirmedTx, Update, WalletEvent,
+    AddressInfo, Balance, BlockId, CanonicalTx, ChangeSet, ConfirmationBlockTime, EvictedTx,
+    FullScanRequestBuilder, KeychainAndIndex, KeychainKind, LocalOutput, Policy, ScriptAmount,
+    SelectionPreview, SentAndReceivedValues, SignOptions, SignerInfo, SyncRequestBuilder,
+    UnconfirmedTx, Update, WalletEvent,
 };
 
-use bdk_wallet::bitcoin::Network;
+use bdk_wallet::bitcoin::bip32::{ChildNumber, Fingerprint};
+use bdk_wallet::bitcoin::constants::COINBASE_MATURITY;
+use bdk_wallet::bitcoin::hashes::Hash;
+use bdk_wallet::bitcoin::secp256k1::{All, Message, Secp256k1};
+use bdk_wallet::bitcoin::sign_message::{signed_msg_hash, MessageSignature};
+use bdk_wallet::bitcoin::Amount as BdkAmount;
+use bdk_wallet::bitcoin::Psbt as BdkPsbt;
+use bdk_wallet::bitcoin::Transaction as BdkTransaction;
+use bdk_wallet::bitcoin::{Network, PrivateKey};
+use bdk_wallet::chain::{CanonicalizationParams, ChainPosition as BdkChainPosition};
+use bdk_wallet::keys::DescriptorSecretKey as BdkDescriptorSecretKey;
+use bdk_wallet::miniscript::descriptor::Wildcard;
+use bdk_wallet::miniscript::ForEachKey;
 #[allow(deprecated)]
 use bdk_wallet::signer::SignOptions as BdkSignOptions;
+use bdk_wallet::signer::{
+    SignerCommon, SignerError as BdkSignerError, SignerId, SignerOrdering, TransactionSigner,
+};
 use bdk_wallet::{PersistedWallet, Wallet as BdkWallet};
 
 use std::ops::DerefMut;
@@ -167,6 +185,34 @@ impl Wallet {
         })
     }
 
+    /// Build Wallet by loading from a JSON `ChangeSet` the caller stored themselves, as produced by
+    /// `ChangeSet::to_json`.
+    ///
+    /// The `ChangeSet` must hold the whole wallet, including its descriptors and network: merge
+    /// every stored `ChangeSet` with `ChangeSet::from_merge`, not only the last one taken with
+    /// `Wallet::take_staged`.
+    ///
+    /// The loaded wallet has no store behind it, so later changes are only staged. Fetch them with
+    /// `Wallet::take_staged` (or `Wallet::staged`), serialize them with `ChangeSet::to_json` and
+    /// store them yourself.
+    #[uniffi::constructor(default(lookahead = 25))]
+    pub fn load_from_changeset(
+        descriptor: Arc<Descriptor>,
+        change_descriptor: Arc<Descriptor>,
+        changeset_json: String,
+        lookahead: u32,
+    ) -> Result<Wallet, LoadWithPersistError> {
+        let changeset = ChangeSet::from_json(changeset_json).map_err(|e| {
+            LoadWithPersistError::InvalidChangeSet {
+                error_message: e.to_string(),
+            }
+        })?;
+        let persister = Arc::new(Persister::custom(Arc::new(ChangeSetSnapshot(Arc::new(
+            changeset,
+        )))));
+        Wallet::load(descriptor, change_descriptor, persister, lookahead)
+    }
+
     /// Build a single-descriptor Wallet by loading from persistence.
     ///
     /// Note that the descriptor secret keys are not persisted to the db.
@@ -398,6 +444,18 @@ impl Wallet {
             .collect()
     }
 
+    /// Apply a block fetched from a full node at the given `height`, confirming the relevant
+    /// transactions it contains and staging the changes (but not persisting them).
+    ///
+    /// The block must connect to the wallet's local chain through its previous block hash. A block
+    /// that leaves a gap the wallet cannot bridge returns a `CannotConnectError`, in which case
+    /// the blocks in between should be applied first.
+    pub fn apply_block(&self, block: Arc<Block>, height: u32) -> Result<(), CannotConnectError> {
+        self.get_wallet()
+            .apply_block(&block.0, height)
+            .map_err(CannotConnectError::from)
+    }
+
     /// The derivation index of this wallet. It will return `None` if it has not derived any addresses.
     /// Otherwise, it will return the index of the highest address it has derived.
     pub fn derivation_index(&self, keychain: KeychainKind) -> Option<u32> {
@@ -431,11 +489,117 @@ impl Wallet {
         Balance::from(bdk_balance)
     }
 
+    /// Return the balance of the outputs belonging to a single keychain, separated into the same
+    /// categories as [`Wallet::balance`].
+    ///
+    /// As with the wallet balance, unconfirmed outputs are only trusted on the internal keychain.
+    pub fn balance_for_keychain(&self, keychain: KeychainKind) -> Balance {
+        let wallet = self.get_wallet();
+        let outpoints = wallet
+            .spk_index()
+            .outpoints()
+            .iter()
+            .filter(|((spk_keychain, _), _)| *spk_keychain == keychain)
+            .cloned();
+        let balance = wallet.tx_graph().balance(
+            wallet.local_chain(),
+            wallet.latest_checkpoint().block_id(),
+            CanonicalizationParams::default(),
+            outpoints,
+            |&(keychain, _), _| keychain == KeychainKind::Internal,
+        );
+        Balance::from(balance)
+    }
+
     /// Return whether or not a `script` is part of this wallet (either internal or external).
     pub fn is_mine(&self, script: Arc<Script>) -> bool {
         self.get_wallet().is_mine(script.0.clone())
     }
 
+    /// Sign a message with the key behind one of the wallet's addresses, in the legacy
+    /// `Bitcoin Signed Message` format. Returns the base64 encoded signature, which can be checked
+    /// with [`Address::verify_message`].
+    ///
+    /// Only addresses controlled by a single key, like P2PKH and P2WPKH, can sign messages.
+    pub fn sign_message(
+        &self,
+        message: String,
+        address: Arc<Address>,
+    ) -> Result<String, SignerError> {
+        let wallet = self.get_wallet();
+        let (keychain, index) = wallet
+            .derivation_of_spk(address.0.script_pubkey())
+            .ok_or(SignerError::MissingKey)?;
+        let secp = Secp256k1::new();
+        let private_key = wallet
+            .get_signers(keychain)
+            .signers()
+            .into_iter()
+            .filter_map(|signer| signer.descriptor_secret_key())
+            .filter_map(|key| derive_private_key(&secp, key, index))
+            .find(|key| address.0.is_related_to_pubkey(&key.public_key(&secp)))
+            .ok_or(SignerError::MissingKey)?;
+
+        let message = Message::from_digest(signed_msg_hash(&message).to_byte_array());
+        let signature = secp.sign_ecdsa_recoverable(&message, &private_key.inner);
+        Ok(MessageSignature::new(signature, private_key.compressed).to_base64())
+    }
+
+    /// List the keys of the `keychain` descriptor by master key fingerprint, flagging those the
+    /// wallet has a signer for. A 2-of-2 multisig wallet holding one of the keys reports two keys,
+    /// one of them with a secret.
+    pub fn signers(&self, keychain: KeychainKind) -> Vec<SignerInfo> {
+        let wallet = self.get_wallet();
+        let secret_fingerprints: Vec<Fingerprint> = wallet
+            .get_signers(keychain)
+            .signers()
+            .into_iter()
+            .filter_map(|signer| match signer.id(wallet.secp_ctx()) {
+                SignerId::Fingerprint(fingerprint) => Some(fingerprint),
+                // single keys without an origin are fingerprinted with the start of their hash
+                SignerId::PkHash(hash) => {
+                    let bytes = hash.as_byte_array();
+                    Some(Fingerprint::from([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                }
+                SignerId::Dummy(_) => None,
+            })
+            .collect();
+
+        let mut fingerprints: Vec<Fingerprint> = Vec::new();
+        wallet.public_descriptor(keychain).for_each_key(|key| {
+            let fingerprint = key.master_fingerprint();
+            if !fingerprints.contains(&fingerprint) {
+                fingerprints.push(fingerprint);
+            }
+            true
+        });
+        fingerprints
+            .into_iter()
+            .map(|fingerprint| SignerInfo {
+                fingerprint: fingerprint.to_string(),
+                has_secret: secret_fingerprints.contains(&fingerprint),
+            })
+            .collect()
+    }
+
+    /// Register an external signer, such as a hardware wallet, for the `keychain` descriptor.
+    ///
+    /// `sign` calls the wallet's signers in increasing `ordering`, the software signers of the
+    /// descriptor having ordering 100. The signer is called while the wallet is locked, so it must
+    /// not call back into this wallet.
+    pub fn add_signer(
+        &self,
+        keychain: KeychainKind,
+        ordering: u32,
+        signer: Arc<dyn ForeignSigner>,
+    ) {
+        self.get_wallet().add_signer(
+            keychain,
+            SignerOrdering(ordering as usize),
+            Arc::new(ForeignTransactionSigner(signer)),
+        );
+    }
+
     /// Sign a transaction with all the wallet's signers, in the order specified by every signer's
     /// [`SignerOrdering`]. This function returns the `Result` type with an encapsulated `bool` that
     /// has the value true if the PSBT was finalized, or false otherwise.
@@ -520,8 +684,32 @@ impl Wallet {
     ///   confirmed or unconfirmed. If the transaction is confirmed, the anchor which proves the
     ///   confirmation is provided. If the transaction is unconfirmed, the unix timestamp of when
     ///   the transaction was last seen in the mempool is provided.
-    pub fn get_tx(&self, txid: Arc<Txid>) -> Result<Option<CanonicalTx>, TxidParseError> {
-        Ok(self.get_wallet().get_tx(txid.0).map(|tx| tx.into()))
+    ///
+    /// Returns `None` if the wallet does not know about `txid`.
+    pub fn get_tx(&self, txid: Arc<Txid>) -> Option<CanonicalTx> {
+        self.get_wallet().get_tx(txid.0).map(|tx| tx.into())
+    }
+
+    /// The block and time confirming the wallet transaction `txid`, read from the local chain
+    /// without a network call. Returns `None` if the transaction is unknown or unconfirmed.
+    ///
+    /// When the transaction is only known to be confirmed through a descendant, this is the
+    /// descendant's confirmation, an upper bound of the transaction's own.
+    pub fn get_confirmation_block_time(&self, txid: Arc<Txid>) -> Option<ConfirmationBlockTime> {
+        match self.get_wallet().get_tx(txid.0)?.chain_position {
+            BdkChainPosition::Confirmed { anchor, .. } => Some(anchor.into()),
+            BdkChainPosition::Unconfirmed { .. } => None,
+        }
+    }
+
+    /// Whether a wallet transaction can be replaced with a [`BumpFeeTxBuilder`]: it must be known
+    /// to the wallet, unconfirmed and signal replaceability as described in BIP-125.
+    ///
+    /// [`BumpFeeTxBuilder`]: crate::tx_builder::BumpFeeTxBuilder
+    pub fn can_rbf(&self, txid: Arc<Txid>) -> bool {
+        self.get_wallet().get_tx(txid.0).is_some_and(|tx| {
+            !tx.chain_position.is_confirmed() && tx.tx_node.tx.is_explicitly_rbf()
+        })
     }
 
     /// Inserts a [`TxOut`] at [`OutPoint`] into the wallet's transaction graph.
@@ -573,11 +761,124 @@ impl Wallet {
             .map_err(|e| e.into())
     }
 
+    /// Preview the coins the wallet would select to pay `recipients` at `fee_rate`, along with the
+    /// change and fee, without building a PSBT.
+    ///
+    /// The wallet state is left unchanged: the change address a `TxBuilder` would reveal is only
+    /// peeked at, so a later `TxBuilder` with the same recipients and fee rate picks the same
+    /// change address. Coin selection uses the default algorithm, which falls back to a random
+    /// draw, so the selected coins can differ between calls when several combinations work.
+    pub fn preview_selection(
+        &self,
+        recipients: Vec<ScriptAmount>,
+        fee_rate: Arc<FeeRate>,
+    ) -> Result<SelectionPreview, CreateTxError> {
+        let mut wallet = self.get_wallet();
+        // mirror how the wallet picks the change address when building a transaction
+        let change_keychain = match wallet.spk_index().get_descriptor(KeychainKind::Internal) {
+            Some(_) => KeychainKind::Internal,
+            None => KeychainKind::External,
+        };
+        let change_index = wallet
+            .spk_index()
+            .unused_keychain_spks(change_keychain)
+            .next()
+            .map(|(index, _)| index)
+            .or_else(|| {
+                wallet
+                    .spk_index()
+                    .next_index(change_keychain)
+                    .map(|(index, _)| index)
+            });
+        let change_script =
+            change_index.map(|index| wallet.peek_address(change_keychain, index).script_pubkey());
+
+        let mut tx_builder = wallet.build_tx();
+        for recipient in &recipients {
+            tx_builder.add_recipient(recipient.script.0.clone(), recipient.amount.0);
+        }
+        tx_builder.fee_rate(fee_rate.0);
+        // draining to the change script directly keeps the builder from revealing it
+        if let Some(script) = &change_script {
+            tx_builder.drain_to(script.clone());
+        }
+        let psbt = tx_builder.finish();
+        let tx = psbt.map_err(CreateTxError::from)?.unsigned_tx;
+
+        let mut total_input = BdkAmount::ZERO;
+        let mut selected = Vec::with_capacity(tx.input.len());
+        for txin in &tx.input {
+            if let Some(utxo) = wallet.get_utxo(txin.previous_output) {
+                total_input += utxo.txout.value;
+            }
+            selected.push(txin.previous_output.into());
+        }
+        let total_output: BdkAmount = tx.output.iter().map(|txout| txout.value).sum();
+        let change = tx
+            .output
+            .iter()
+            .find(|txout| Some(&txout.script_pubkey) == change_script.as_ref())
+            .map(|txout| Arc::new(Amount(txout.value)));
+
+        Ok(SelectionPreview {
+            selected,
+            total_input: Arc::new(Amount(total_input)),
+            change,
+            fee: Arc::new(Amount(
+                total_input
+                    .checked_sub(total_output)
+                    .unwrap_or(BdkAmount::ZERO),
+            )),
+        })
+    }
+
+    /// Informs the wallet that you no longer intend to broadcast a tx that was built from it.
+    ///
+    /// This frees up the change address used when creating the tx for use in future transactions.
+    pub fn cancel_tx(&self, tx: &Transaction) {
+        let mut wallet = self.get_wallet();
+        let tx: BdkTransaction = tx.into();
+        for txout in tx.output {
+            if let Some(&(keychain, index)) = wallet.spk_index().index_of_spk(txout.script_pubkey) {
+                wallet.unmark_used(keychain, index);
+            }
+        }
+    }
+
     /// Return the list of unspent outputs of this wallet.
     pub fn list_unspent(&self) -> Vec<LocalOutput> {
         self.get_wallet().list_unspent().map(|o| o.into()).collect()
     }
 
+    /// Return the unspent outputs with at least `min_confirmations` confirmations at
+    /// `current_height`. Unconfirmed outputs have zero confirmations, and coinbase outputs are
+    /// left out until they mature after 100 confirmations.
+    pub fn list_unspent_confirmed(
+        &self,
+        min_confirmations: u32,
+        current_height: u32,
+    ) -> Vec<LocalOutput> {
+        let wallet = self.get_wallet();
+        wallet
+            .list_unspent()
+            .filter(|utxo| {
+                let confirmations = match utxo.chain_position {
+                    BdkChainPosition::Confirmed { anchor, .. } => current_height
+                        .checked_sub(anchor.block_id.height)
+                        .map_or(0, |age| age + 1),
+                    BdkChainPosition::Unconfirmed { .. } => 0,
+                };
+                let is_coinbase = wallet
+                    .tx_graph()
+                    .get_tx(utxo.outpoint.txid)
+                    .is_some_and(|tx| tx.is_coinbase());
+                confirmations >= min_confirmations
+                    && (!is_coinbase || confirmations >= COINBASE_MATURITY)
+            })
+            .map(|utxo| utxo.into())
+            .collect()
+    }
+
     /// List the locked outpoints.
     pub fn list_locked_outpoints(&self) -> Vec<OutPoint> {
         self.get_wallet()
@@ -723,8 +1024,74 @@ impl Wallet {
     }
 }
 
+/// A signer living outside of the library, such as a hardware wallet or a remote signing
+/// service, see `Wallet::add_signer`.
+#[uniffi::export(with_foreign)]
+pub trait ForeignSigner: Send + Sync {
+    /// Add signatures to `psbt` and return it.
+    fn sign_psbt(&self, psbt: Arc<Psbt>) -> Result<Arc<Psbt>, SignerError>;
+}
+
+/// Adapts a `ForeignSigner` to the signers bdk calls during `Wallet::sign`.
+struct ForeignTransactionSigner(Arc<dyn ForeignSigner>);
+
+impl std::fmt::Debug for ForeignTransactionSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ForeignTransactionSigner")
+    }
+}
+
+impl SignerCommon for ForeignTransactionSigner {
+    fn id(&self, _secp: &Secp256k1<All>) -> SignerId {
+        // distinct foreign signers must not replace each other in the signers container
+        SignerId::Dummy(Arc::as_ptr(&self.0) as *const () as usize as u64)
+    }
+}
+
+impl TransactionSigner for ForeignTransactionSigner {
+    fn sign_transaction(
+        &self,
+        psbt: &mut BdkPsbt,
+        _sign_options: &BdkSignOptions,
+        _secp: &Secp256k1<All>,
+    ) -> Result<(), BdkSignerError> {
+        let signed = self
+            .0
+            .sign_psbt(Arc::new(psbt.clone().into()))
+            .map_err(|e| BdkSignerError::External(e.to_string()))?;
+        *psbt = signed.0.lock().unwrap().clone();
+        Ok(())
+    }
+}
+
 impl Wallet {
     pub(crate) fn get_wallet(&self) -> MutexGuard<'_, PersistedWallet<PersistenceType>> {
         self.inner_mutex.lock().expect("wallet")
     }
 }
+
+fn derive_private_key(
+    secp: &Secp256k1<All>,
+    key: BdkDescriptorSecretKey,
+    index: u32,
+) -> Option<PrivateKey> {
+    match key {
+        BdkDescriptorSecretKey::Single(single) => Some(single.key),
+        BdkDescriptorSecretKey::XPrv(xkey) => {
+            let path = match xkey.wildcard {
+                Wildcard::None => xkey.derivation_path,
+                Wildcard::Unhardened => xkey
+                    .derivation_path
+                    .child(ChildNumber::from_normal_idx(index).ok()?),
+                Wildcard::Hardened => xkey
+                    .derivation_path
+                    .child(ChildNumber::from_hardened_idx(index).ok()?),
+            };
+            xkey.xkey
+                .derive_priv(secp, &path)
+                .ok()
+                .map(|xpriv| xpriv.to_priv())
+        }
+        BdkDescriptorSecretKey::MultiXPrv(_) => None,
+    }
+}